@@ -1,6 +1,6 @@
 extern crate apt_release_file;
 
-use apt_release_file::DistRelease;
+use apt_release_file::{DistRelease, HashAlgorithm};
 
 const RELEASE: &str = include_str!("../tests/Release");
 
@@ -9,7 +9,7 @@ pub fn main() {
     println!("{:#?}", release);
 
     println!("# Base components in MD5Sum");
-    let components = &release.sums["MD5Sum"];
+    let components = &release.sums[&HashAlgorithm::Md5];
     for components in &components.base {
         println!("{:#?}", components);
     }
@@ -0,0 +1,314 @@
+extern crate apt_release_file;
+extern crate deb_architectures;
+extern crate serde_json;
+
+use apt_release_file::{Compression, DistRelease, EntryVariant, InRelease, ReleaseFile};
+use deb_architectures::Architecture;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::exit;
+use std::{env, fmt, fs};
+
+const USAGE: &str = "Usage: apt-release <command> [<args>]
+
+Commands:
+    show <release>              Print a summary of the header
+    list <release> [<filters>]  List every file, with its size and strongest checksum
+    verify <dists dir>          Check the files of the release file in the directory
+    diff <old> <new>            List the fields and files which differ
+    json <release>              Print the release file as JSON
+
+Filters of list:
+    --component <name>          Files of a component, such as main
+    --arch <name>               Indices of an architecture, such as amd64
+    --variant <name>            Files of a variant, such as binary or contents
+    --compression <name>        Files with a compression, such as xz or none
+
+A release file may be either a Release or a clearsigned InRelease file. The verify
+command reads the InRelease file of the directory, or the Release file if there is none.
+
+Exit status:
+    0   Success
+    1   verify found files which do not match, or diff found differences
+    2   The arguments were invalid
+    3   A file could not be read or written, or is not a valid release file";
+
+// The exit status of each outcome, so that CI jobs may tell failures apart.
+const SUCCESS: i32 = 0;
+const DIFFERENT: i32 = 1;
+const USAGE_ERROR: i32 = 2;
+const INPUT_ERROR: i32 = 3;
+
+enum CliError {
+    Usage(String),
+    Input(String, io::Error),
+    Output(io::Error),
+}
+
+impl From<io::Error> for CliError {
+    fn from(why: io::Error) -> Self {
+        CliError::Output(why)
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CliError::Usage(ref why) => write!(fmt, "{}\n\n{}", why, USAGE),
+            CliError::Input(ref path, ref why) => write!(fmt, "{}: {}", path, why),
+            CliError::Output(ref why) => write!(fmt, "failed to write output: {}", why),
+        }
+    }
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+
+    let stdout = io::stdout();
+
+    let status = match run(&args, &mut stdout.lock()) {
+        Ok(status) => status,
+        // The output was closed early, such as by `head`.
+        Err(CliError::Output(ref why)) if why.kind() == io::ErrorKind::BrokenPipe => SUCCESS,
+        Err(why) => {
+            eprintln!("apt-release: {}", why);
+            match why {
+                CliError::Usage(_) => USAGE_ERROR,
+                CliError::Input(..) | CliError::Output(_) => INPUT_ERROR,
+            }
+        }
+    };
+
+    exit(status);
+}
+
+fn run<W: Write>(args: &[String], out: &mut W) -> Result<i32, CliError> {
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => return Err(CliError::Usage("missing a command".into())),
+    };
+
+    match (command, args) {
+        ("-h", _) | ("--help", _) | ("help", _) => {
+            writeln!(out, "{}", USAGE)?;
+            Ok(SUCCESS)
+        }
+        ("show", [path]) => Ok(show(out, &load(path)?)?),
+        ("list", [path, filters @ ..]) => {
+            let filter = Filter::parse(filters)?;
+            Ok(list(out, &load(path)?, &filter)?)
+        }
+        ("verify", [dir]) => verify(out, Path::new(dir)),
+        ("diff", [old, new]) => Ok(diff(out, &load(old)?, &load(new)?)?),
+        ("json", [path]) => Ok(json(out, &load(path)?)?),
+        ("show", _) | ("list", _) | ("verify", _) | ("diff", _) | ("json", _) => Err(
+            CliError::Usage(format!("wrong number of arguments for {}", command)),
+        ),
+        (other, _) => Err(CliError::Usage(format!("unknown command: {}", other))),
+    }
+}
+
+// Reads a release file, which may be clearsigned.
+fn load<P: AsRef<Path>>(path: P) -> Result<DistRelease, CliError> {
+    let path = path.as_ref();
+    let input = |why| CliError::Input(path.display().to_string(), why);
+
+    let text = fs::read_to_string(path).map_err(input)?;
+    if text.starts_with("-----BEGIN PGP SIGNED MESSAGE-----") {
+        text.parse::<InRelease>()
+            .map(|inrelease| inrelease.release)
+            .map_err(|why| input(io::Error::from(why)))
+    } else {
+        text.parse::<DistRelease>()
+            .map_err(|why| input(io::Error::from(why)))
+    }
+}
+
+fn show<W: Write>(out: &mut W, release: &DistRelease) -> io::Result<i32> {
+    let mut print = |key: &str, value: Option<String>| match value {
+        Some(value) => writeln!(out, "{:<16} {}", [key, ":"].concat(), value),
+        None => Ok(()),
+    };
+
+    fn yes_no(value: Option<bool>) -> Option<String> {
+        value.map(|value| if value { "yes" } else { "no" }.to_owned())
+    }
+
+    print("Origin", release.origin.clone())?;
+    print("Label", release.label.clone())?;
+    print("Suite", release.suite.clone())?;
+    print("Codename", release.codename.clone())?;
+    print("Version", release.version.clone())?;
+    print("Date", release.date.map(|date| date.to_rfc2822()))?;
+    print(
+        "Valid-Until",
+        release.valid_until.map(|date| date.to_rfc2822()),
+    )?;
+    print(
+        "Architectures",
+        release.architectures.as_ref().map(|archs| archs.join(" ")),
+    )?;
+    print(
+        "Components",
+        release
+            .components
+            .as_ref()
+            .map(|components| components.join(" ")),
+    )?;
+    print("Acquire-By-Hash", yes_no(release.acquire_by_hash))?;
+    print("NotAutomatic", yes_no(release.not_automatic))?;
+    print(
+        "Checksums",
+        Some(
+            release
+                .sums
+                .keys()
+                .map(|algorithm| algorithm.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+    )?;
+    print("Files", Some(release.files().len().to_string()))?;
+
+    Ok(SUCCESS)
+}
+
+// Selects the files that the list command prints.
+#[derive(Default)]
+struct Filter {
+    component: Option<String>,
+    arch: Option<Architecture>,
+    variant: Option<String>,
+    compression: Option<Compression>,
+}
+
+impl Filter {
+    fn parse(args: &[String]) -> Result<Self, CliError> {
+        let mut filter = Filter::default();
+        let mut args = args.iter();
+
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| CliError::Usage(format!("missing a value for {}", flag)))?;
+
+            match flag.as_str() {
+                "--component" => filter.component = Some(value.clone()),
+                "--arch" => {
+                    let arch = value
+                        .parse::<Architecture>()
+                        .map_err(|_| CliError::Usage(format!("unknown architecture: {}", value)))?;
+                    filter.arch = Some(arch);
+                }
+                "--variant" => filter.variant = Some(value.clone()),
+                "--compression" => {
+                    filter.compression = Some(match value.as_str() {
+                        "none" => Compression::None,
+                        other => Compression::from_extension(other),
+                    })
+                }
+                other => return Err(CliError::Usage(format!("unknown filter: {}", other))),
+            }
+        }
+
+        Ok(filter)
+    }
+
+    fn matches(&self, file: &ReleaseFile) -> bool {
+        let variant = file.variant();
+
+        if let Some(ref component) = self.component {
+            if file.component() != Some(component.as_str()) {
+                return false;
+            }
+        }
+
+        if self.arch.is_some() && variant.as_ref().and_then(|v| v.architecture()) != self.arch {
+            return false;
+        }
+
+        if let Some(ref name) = self.variant {
+            if variant.as_ref().map(variant_name) != Some(name.as_str()) {
+                return false;
+            }
+        }
+
+        if let Some(ref compression) = self.compression {
+            let found = match variant.as_ref().and_then(|v| v.compression()) {
+                Some(compression) => compression.clone(),
+                None => Compression::from_path(&file.path),
+            };
+
+            if found != *compression {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+// The name of a variant, as it is written in JSON.
+fn variant_name(variant: &EntryVariant) -> &'static str {
+    match *variant {
+        EntryVariant::Binary(..) => "binary",
+        EntryVariant::Contents(..) => "contents",
+        EntryVariant::ComponentContents(..) => "component_contents",
+        EntryVariant::Commands(..) => "commands",
+        EntryVariant::ByHash(_) => "by_hash",
+        EntryVariant::DebianInstaller(_) => "debian_installer",
+        EntryVariant::Pdiff(_) => "pdiff",
+        EntryVariant::Dep11(_) => "dep11",
+        EntryVariant::Source(_) => "source",
+        EntryVariant::I18n(_) => "i18n",
+    }
+}
+
+fn list<W: Write>(out: &mut W, release: &DistRelease, filter: &Filter) -> io::Result<i32> {
+    for file in release.files() {
+        if !filter.matches(&file) {
+            continue;
+        }
+
+        let sum = file.strongest_sum().map_or("-", |(_, sum)| sum);
+        writeln!(out, "{} {:>16} {}", sum, file.size, file.path)?;
+    }
+
+    Ok(SUCCESS)
+}
+
+fn verify<W: Write>(out: &mut W, dir: &Path) -> Result<i32, CliError> {
+    let path = match dir.join("InRelease") {
+        ref inrelease if inrelease.exists() => inrelease.clone(),
+        _ => dir.join("Release"),
+    };
+
+    let release = load(&path)?;
+    let mismatches = release
+        .verify_dir(dir)
+        .map_err(|why| CliError::Input(dir.display().to_string(), why))?;
+
+    for mismatch in &mismatches {
+        writeln!(out, "{}", mismatch)?;
+    }
+
+    if mismatches.is_empty() {
+        writeln!(out, "{} files match", release.files().len())?;
+        Ok(SUCCESS)
+    } else {
+        Ok(DIFFERENT)
+    }
+}
+
+fn diff<W: Write>(out: &mut W, old: &DistRelease, new: &DistRelease) -> io::Result<i32> {
+    let diff = DistRelease::diff(old, new);
+    write!(out, "{}", diff)?;
+
+    Ok(if diff.is_empty() { SUCCESS } else { DIFFERENT })
+}
+
+fn json<W: Write>(out: &mut W, release: &DistRelease) -> io::Result<i32> {
+    serde_json::to_writer_pretty(&mut *out, release)?;
+    writeln!(out)?;
+    Ok(SUCCESS)
+}
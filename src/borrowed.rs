@@ -0,0 +1,450 @@
+use super::error::span;
+use super::time::get_time;
+use super::{
+    DistRelease, EntryComponents, EntryError, HashAlgorithm, ParseError, Position, ReleaseEntry,
+};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+// The header fields which are parsed into typed values.
+#[derive(Copy, Clone)]
+enum Variant {
+    AcquireByHash,
+    Archs,
+    ButAutomaticUpgrades,
+    Changelogs,
+    Codename,
+    Components,
+    Date,
+    Description,
+    Label,
+    NoSupportForArchAll,
+    NotAutomatic,
+    Origin,
+    SignedBy,
+    Snapshots,
+    Suite,
+    ValidUntil,
+    Version,
+}
+
+const FIELDS: &[(&str, Variant)] = &[
+    ("Acquire-By-Hash", Variant::AcquireByHash),
+    ("Architectures", Variant::Archs),
+    ("ButAutomaticUpgrades", Variant::ButAutomaticUpgrades),
+    ("Changelogs", Variant::Changelogs),
+    ("Codename", Variant::Codename),
+    ("Components", Variant::Components),
+    ("Date", Variant::Date),
+    ("Description", Variant::Description),
+    ("Label", Variant::Label),
+    (
+        "No-Support-for-Architecture-all",
+        Variant::NoSupportForArchAll,
+    ),
+    ("NotAutomatic", Variant::NotAutomatic),
+    ("Origin", Variant::Origin),
+    ("Signed-By", Variant::SignedBy),
+    ("Snapshots", Variant::Snapshots),
+    ("Suite", Variant::Suite),
+    ("Valid-Until", Variant::ValidUntil),
+    ("Version", Variant::Version),
+];
+
+// Whether a header field is parsed into a typed value, rather than only kept as it is written.
+pub(crate) fn is_known_field(key: &str) -> bool {
+    FIELDS.iter().any(|&(id, _)| id == key)
+}
+
+/// A release file which borrows its strings from the text that it was parsed from.
+///
+/// Parsing one allocates only the maps and vectors which hold the slices, so it is much cheaper
+/// than parsing a `DistRelease` when many release files are inspected and then discarded.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DistReleaseRef<'a> {
+    pub acquire_by_hash: Option<bool>,
+    pub architectures: Option<Vec<&'a str>>,
+    pub but_automatic_upgrades: Option<bool>,
+    pub changelogs: Option<&'a str>,
+    pub codename: Option<&'a str>,
+    pub components: Option<Vec<&'a str>>,
+    pub date: Option<DateTime<Utc>>,
+    pub description: Option<&'a str>,
+    pub label: Option<&'a str>,
+    pub no_support_for_architecture_all: Option<&'a str>,
+    pub not_automatic: Option<bool>,
+    pub origin: Option<&'a str>,
+    pub signed_by: Option<Vec<&'a str>>,
+    pub snapshots: Option<&'a str>,
+    pub suite: Option<&'a str>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub version: Option<&'a str>,
+    /// Every field of the header, in the order that it was defined, as it is written.
+    pub fields: Vec<(&'a str, &'a str)>,
+    /// The checksum sections, in the order that they were defined.
+    pub sections: Vec<HashAlgorithm>,
+    pub sums: BTreeMap<HashAlgorithm, EntryComponentsRef<'a>>,
+}
+
+impl<'a> DistReleaseRef<'a> {
+    /// Parses a release file, borrowing every string from the input.
+    pub fn parse(input: &'a str) -> Result<Self, ParseError> {
+        let mut release = DistReleaseRef::default();
+
+        fn get_vec(value: &str) -> Option<Vec<&str>> {
+            Some(value.split_whitespace().collect())
+        }
+
+        fn get_bool(value: &str) -> Result<Option<bool>, String> {
+            match value {
+                "yes" => Ok(Some(true)),
+                "no" => Ok(Some(false)),
+                other => Err(format!("expected yes or no: {}", other)),
+            }
+        }
+
+        // The checksum section that entries are currently being collected for.
+        let mut active: Option<(HashAlgorithm, EntryComponentsRef<'a>)> = None;
+        let mut defined: Vec<&str> = Vec::new();
+
+        for (number, line) in input.lines().enumerate() {
+            let position = |span| Position::new(number + 1, line, span);
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if line.starts_with(' ') || line.starts_with('\t') {
+                let (hash, components) = match active {
+                    Some((ref hash, ref mut components)) => (hash, components),
+                    None => {
+                        return Err(ParseError::EntryOutsideSection {
+                            position: position(0..line.len()),
+                        })
+                    }
+                };
+
+                components.insert(ReleaseEntryRef::parse_row(hash, number + 1, line)?);
+
+                continue;
+            }
+
+            let (key, value) = match line.find(':') {
+                Some(pos) => (&line[..pos], line[pos + 1..].trim()),
+                None => {
+                    return Err(ParseError::UnknownKey {
+                        position: position(0..line.len()),
+                    })
+                }
+            };
+
+            if let Some((hash, components)) = active.take() {
+                release.insert_section(hash, components);
+            }
+
+            let variant = FIELDS.iter().find(|&&(id, _)| id == key);
+
+            if variant.is_some() || value.is_empty() {
+                if defined.contains(&key) {
+                    return Err(ParseError::DuplicateField {
+                        position: position(0..key.len()),
+                        field: key.to_owned(),
+                    });
+                }
+
+                defined.push(key);
+            }
+
+            if variant.is_none() && value.is_empty() {
+                active = Some((HashAlgorithm::from(key), EntryComponentsRef::default()));
+                continue;
+            }
+
+            release.fields.push((key, value));
+
+            let variant = match variant {
+                Some(&(_, variant)) => variant,
+                None => continue,
+            };
+
+            let invalid_date = |reason| ParseError::InvalidDate {
+                position: position(span(line, value)),
+                reason,
+            };
+
+            let invalid_value = |reason| ParseError::InvalidValue {
+                position: position(span(line, value)),
+                reason,
+            };
+
+            match variant {
+                Variant::AcquireByHash => {
+                    release.acquire_by_hash = get_bool(value).map_err(invalid_value)?
+                }
+                Variant::Archs => release.architectures = get_vec(value),
+                Variant::ButAutomaticUpgrades => {
+                    release.but_automatic_upgrades = get_bool(value).map_err(invalid_value)?
+                }
+                Variant::Changelogs => release.changelogs = Some(value),
+                Variant::Codename => release.codename = Some(value),
+                Variant::Components => release.components = get_vec(value),
+                Variant::Date => release.date = Some(get_time(value).map_err(invalid_date)?),
+                Variant::Description => release.description = Some(value),
+                Variant::Label => release.label = Some(value),
+                Variant::NoSupportForArchAll => {
+                    release.no_support_for_architecture_all = Some(value)
+                }
+                Variant::NotAutomatic => {
+                    release.not_automatic = get_bool(value).map_err(invalid_value)?
+                }
+                Variant::Origin => release.origin = Some(value),
+                Variant::SignedBy => {
+                    release.signed_by = Some(
+                        value
+                            .split(|c: char| c == ',' || c.is_whitespace())
+                            .filter(|key| !key.is_empty())
+                            .collect(),
+                    )
+                }
+                Variant::Snapshots => release.snapshots = Some(value),
+                Variant::Suite => release.suite = Some(value),
+                Variant::ValidUntil => {
+                    release.valid_until = Some(get_time(value).map_err(invalid_date)?)
+                }
+                Variant::Version => release.version = Some(value),
+            }
+        }
+
+        if let Some((hash, components)) = active.take() {
+            release.insert_section(hash, components);
+        }
+
+        Ok(release)
+    }
+
+    /// Fields which are not otherwise recognized, in the order that they were defined.
+    pub fn extra_fields<'b>(&'b self) -> impl Iterator<Item = (&'a str, &'a str)> + 'b {
+        self.fields
+            .iter()
+            .cloned()
+            .filter(|&(key, _)| !is_known_field(key))
+    }
+
+    /// Copies every borrowed string, so that the release file outlives its input.
+    pub fn into_owned(self) -> DistRelease {
+        fn owned_vec(values: Option<Vec<&str>>) -> Option<Vec<String>> {
+            values.map(|values| values.into_iter().map(String::from).collect())
+        }
+
+        DistRelease {
+            acquire_by_hash: self.acquire_by_hash,
+            architectures: owned_vec(self.architectures),
+            but_automatic_upgrades: self.but_automatic_upgrades,
+            changelogs: self.changelogs.map(String::from),
+            codename: self.codename.map(String::from),
+            components: owned_vec(self.components),
+            date: self.date,
+            description: self.description.map(String::from),
+            label: self.label.map(String::from),
+            no_support_for_architecture_all: self.no_support_for_architecture_all.map(String::from),
+            not_automatic: self.not_automatic,
+            origin: self.origin.map(String::from),
+            signed_by: owned_vec(self.signed_by),
+            snapshots: self.snapshots.map(String::from),
+            suite: self.suite.map(String::from),
+            valid_until: self.valid_until,
+            version: self.version.map(String::from),
+            fields: self
+                .fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+            sections: self.sections,
+            sums: self
+                .sums
+                .into_iter()
+                .map(|(hash, components)| (hash, components.into_owned()))
+                .collect(),
+        }
+    }
+
+    // Sections without any entries are kept, so that they are written back.
+    fn insert_section(&mut self, hash: HashAlgorithm, components: EntryComponentsRef<'a>) {
+        self.sections.push(hash.clone());
+        self.sums.insert(hash, components);
+    }
+}
+
+/// The entries of a checksum section, borrowed from a release file.
+///
+/// Entries are grouped in the same way as those of `EntryComponents`.
+#[derive(Debug, Default, Clone, Hash, PartialEq)]
+pub struct EntryComponentsRef<'a> {
+    pub base: BTreeMap<&'a str, Vec<ReleaseEntryRef<'a>>>,
+    pub components: BTreeMap<&'a str, BTreeMap<&'a str, Vec<ReleaseEntryRef<'a>>>>,
+    /// The paths of the entries, relative to the `dists/<suite>/` directory, in the order that
+    /// they were inserted.
+    pub order: Vec<&'a str>,
+}
+
+impl<'a> EntryComponentsRef<'a> {
+    pub fn is_empty(&self) -> bool {
+        self.base.is_empty() && self.components.is_empty()
+    }
+
+    /// Adds an entry to the base, or to the component that its path begins with.
+    pub fn insert(&mut self, mut entry: ReleaseEntryRef<'a>) {
+        let path = entry.path;
+        self.order.push(path);
+
+        let base = match path.find('.') {
+            Some(pos) => &path[..pos],
+            None => path,
+        };
+
+        match path.find('/') {
+            Some(pos) => {
+                entry.path = &path[pos + 1..];
+
+                self.components
+                    .entry(&path[..pos])
+                    .or_default()
+                    .entry(base)
+                    .or_default()
+                    .push(entry);
+            }
+            None => self.base.entry(base).or_default().push(entry),
+        }
+    }
+
+    /// Looks up an entry by its path, relative to the `dists/<suite>/` directory.
+    pub fn get(&self, path: &str) -> Option<&ReleaseEntryRef<'a>> {
+        let base = match path.find('.') {
+            Some(pos) => &path[..pos],
+            None => path,
+        };
+
+        let (entries, path) = match path.find('/') {
+            Some(pos) => (
+                self.components.get(&path[..pos])?.get(base)?,
+                &path[pos + 1..],
+            ),
+            None => (self.base.get(base)?, path),
+        };
+
+        entries.iter().find(|entry| entry.path == path)
+    }
+
+    /// Iterates every entry, along with the component that the entry belongs to.
+    pub fn iter<'b>(
+        &'b self,
+    ) -> impl Iterator<Item = (Option<&'a str>, &'b ReleaseEntryRef<'a>)> + 'b {
+        let base = self
+            .base
+            .values()
+            .flat_map(|entries| entries.iter())
+            .map(|entry| (None, entry));
+
+        let components = self.components.iter().flat_map(|(&component, entries)| {
+            entries
+                .values()
+                .flat_map(|entries| entries.iter())
+                .map(move |entry| (Some(component), entry))
+        });
+
+        base.chain(components)
+    }
+
+    pub fn into_owned(self) -> EntryComponents {
+        fn owned_entries(entries: Vec<ReleaseEntryRef>) -> Vec<ReleaseEntry> {
+            entries
+                .into_iter()
+                .map(ReleaseEntryRef::into_owned)
+                .collect()
+        }
+
+        EntryComponents {
+            base: self
+                .base
+                .into_iter()
+                .map(|(base, entries)| (base.to_owned(), owned_entries(entries)))
+                .collect(),
+            components: self
+                .components
+                .into_iter()
+                .map(|(component, entries)| {
+                    let entries = entries
+                        .into_iter()
+                        .map(|(base, entries)| (base.to_owned(), owned_entries(entries)))
+                        .collect();
+
+                    (component.to_owned(), entries)
+                })
+                .collect(),
+            order: self.order.into_iter().map(String::from).collect(),
+        }
+    }
+}
+
+/// The hash, size, and path of a file, borrowed from a release file.
+#[derive(Debug, Default, Copy, Clone, Hash, PartialEq)]
+pub struct ReleaseEntryRef<'a> {
+    pub sum: &'a str,
+    pub size: u64,
+    pub path: &'a str,
+}
+
+impl<'a> ReleaseEntryRef<'a> {
+    /// Parses the sum, size, and path of a checksum entry.
+    pub fn parse(input: &'a str) -> Result<Self, EntryError> {
+        let mut iterator = input.split_whitespace();
+
+        Ok(ReleaseEntryRef {
+            sum: iterator.next().ok_or(EntryError::MissingSum)?,
+            size: iterator
+                .next()
+                .ok_or(EntryError::MissingSize)?
+                .parse::<u64>()
+                .map_err(EntryError::InvalidSize)?,
+            path: iterator.next().ok_or(EntryError::MissingPath)?,
+        })
+    }
+
+    pub fn into_owned(self) -> ReleaseEntry {
+        ReleaseEntry {
+            sum: self.sum.to_owned(),
+            size: self.size,
+            path: self.path.to_owned(),
+        }
+    }
+
+    // Parses a checksum row of the given section, such as those of a release file or the `Files`
+    // field of a Sources index.
+    pub(crate) fn parse_row(
+        algorithm: &HashAlgorithm,
+        number: usize,
+        line: &'a str,
+    ) -> Result<Self, ParseError> {
+        let position = |span| Position::new(number, line, span);
+
+        match ReleaseEntryRef::parse(line) {
+            Ok(entry) => match algorithm.validate(entry.sum) {
+                Ok(()) => Ok(entry),
+                Err(reason) => Err(ParseError::InvalidChecksum {
+                    position: position(span(line, entry.sum)),
+                    reason,
+                }),
+            },
+            Err(EntryError::InvalidSize(_)) => {
+                let size = line.split_whitespace().nth(1).unwrap_or(line);
+                Err(ParseError::InvalidSize {
+                    position: position(span(line, size)),
+                })
+            }
+            Err(reason) => Err(ParseError::InvalidEntry {
+                position: position(0..line.len()),
+                reason,
+            }),
+        }
+    }
+}
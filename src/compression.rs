@@ -0,0 +1,200 @@
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use lz4_flex::frame::FrameDecoder;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::{fmt, fs};
+use xz2::read::XzDecoder;
+use xz2::stream::Stream;
+
+/// The compression of an index file, as determined by the extension of its path.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Xz,
+    Bzip2,
+    Lzma,
+    Zstd,
+    Lz4,
+    /// An extension that this crate does not recognize.
+    Other(String),
+}
+
+impl Compression {
+    /// The compressions that apt is able to download, in the order that it prefers them.
+    pub const APT_ORDER: [Compression; 7] = [
+        Compression::Xz,
+        Compression::Bzip2,
+        Compression::Lzma,
+        Compression::Gzip,
+        Compression::Lz4,
+        Compression::Zstd,
+        Compression::None,
+    ];
+
+    /// The rank of this compression in `APT_ORDER`, where lower is preferred.
+    ///
+    /// Unrecognized compressions have no rank.
+    pub fn preference(&self) -> Option<usize> {
+        Compression::APT_ORDER
+            .iter()
+            .position(|compression| compression == self)
+    }
+
+    /// Maps an extension, such as `xz`, to its compression.
+    pub fn from_extension(extension: &str) -> Self {
+        match extension {
+            "" => Compression::None,
+            "gz" => Compression::Gzip,
+            "xz" => Compression::Xz,
+            "bz2" => Compression::Bzip2,
+            "lzma" => Compression::Lzma,
+            "zst" => Compression::Zstd,
+            "lz4" => Compression::Lz4,
+            other => Compression::Other(other.to_owned()),
+        }
+    }
+
+    /// The compression of a file, according to the extension of its file name.
+    ///
+    /// Files without a recognized compression extension are assumed to be uncompressed.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some(extension) => match Compression::from_extension(extension) {
+                Compression::Other(_) => Compression::None,
+                compression => compression,
+            },
+            None => Compression::None,
+        }
+    }
+
+    /// The extension of files with this compression, without the leading `.`.
+    pub fn extension(&self) -> Option<&str> {
+        match *self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Xz => Some("xz"),
+            Compression::Bzip2 => Some("bz2"),
+            Compression::Lzma => Some("lzma"),
+            Compression::Zstd => Some("zst"),
+            Compression::Lz4 => Some("lz4"),
+            Compression::Other(ref extension) => Some(extension),
+        }
+    }
+
+    /// Whether this crate is able to decompress files with this compression.
+    pub fn is_supported(&self) -> bool {
+        !matches!(*self, Compression::Other(_))
+    }
+
+    /// Wraps a reader of compressed data with a reader of the plain content.
+    pub fn decoder<'a, R: Read + Send + 'a>(
+        &self,
+        reader: R,
+    ) -> io::Result<Box<dyn Read + Send + 'a>> {
+        let decoder: Box<dyn Read + Send + 'a> = match *self {
+            Compression::None => Box::new(reader),
+            Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            Compression::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+            Compression::Bzip2 => Box::new(MultiBzDecoder::new(reader)),
+            Compression::Lzma => {
+                let stream = Stream::new_lzma_decoder(u64::MAX).map_err(io::Error::other)?;
+                Box::new(XzDecoder::new_stream(reader, stream))
+            }
+            Compression::Zstd => Box::new(zstd::Decoder::new(reader)?),
+            Compression::Lz4 => Box::new(FrameDecoder::new(reader)),
+            Compression::Other(ref extension) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unsupported compression: {}", extension),
+                ))
+            }
+        };
+
+        Ok(decoder)
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.extension().unwrap_or("none"))
+    }
+}
+
+/// Reads the plain content of an index file, decompressing it if needed.
+///
+/// Both the size of the file on disk and the size of its content are recorded, so that they
+/// may be checked against the rows of the compressed and uncompressed forms in a release file.
+pub struct IndexReader {
+    decoder: Box<dyn Read + Send>,
+    compression: Compression,
+    compressed_size: u64,
+    uncompressed_size: u64,
+}
+
+impl IndexReader {
+    /// Opens an index file, picking its decompressor from the extension of the path.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let compression = Compression::from_path(&path);
+        IndexReader::with_compression(path, compression)
+    }
+
+    pub fn with_compression<P: AsRef<Path>>(path: P, compression: Compression) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let compressed_size = file.metadata()?.len();
+
+        Ok(IndexReader {
+            decoder: compression.decoder(BufReader::new(file))?,
+            compression,
+            compressed_size,
+            uncompressed_size: 0,
+        })
+    }
+
+    pub fn compression(&self) -> &Compression {
+        &self.compression
+    }
+
+    /// The size of the file on disk.
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    /// The number of bytes of content read so far, which is the size of the decompressed file
+    /// once the reader has reached the end.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+}
+
+impl Read for IndexReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.decoder.read(buf)?;
+        self.uncompressed_size += read as u64;
+        Ok(read)
+    }
+}
+
+impl fmt::Debug for IndexReader {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("IndexReader")
+            .field("compression", &self.compression)
+            .field("compressed_size", &self.compressed_size)
+            .field("uncompressed_size", &self.uncompressed_size)
+            .finish()
+    }
+}
+
+// Reads an index file to a string, decompressing it if needed.
+pub(crate) fn read_index<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    match Compression::from_path(&path) {
+        Compression::None => fs::read_to_string(path),
+        compression => {
+            let mut string = String::new();
+            IndexReader::with_compression(path, compression)?.read_to_string(&mut string)?;
+            Ok(string)
+        }
+    }
+}
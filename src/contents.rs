@@ -0,0 +1,151 @@
+use super::{IndexReader, ParseError, Position};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+// Legacy Contents files begin with a header of free-form text, which ends with a line starting
+// with `FILE` and `LOCATION`. Modern files have no header, so only this many lines are held back
+// while looking for the end of one.
+const HEADER_LIMIT: usize = 64;
+
+/// A line of a Contents index, which maps a file to the packages that ship it.
+#[derive(Debug, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContentsEntry {
+    /// The path of the file, without a leading `/`.
+    pub path: String,
+    /// The packages which ship the file, qualified as `[[area/]section/]name`.
+    pub packages: Vec<String>,
+}
+
+impl ContentsEntry {
+    /// The names of the packages which ship the file, without their section qualifiers.
+    pub fn package_names(&self) -> impl Iterator<Item = &str> {
+        self.packages
+            .iter()
+            .map(|package| package.rsplit('/').next().unwrap_or(package))
+    }
+}
+
+/// Reads the entries of a Contents index one line at a time, so that large indices never need
+/// to be held in memory.
+pub struct ContentsReader<R> {
+    reader: R,
+    line: usize,
+    // Lines which were read while looking for a legacy header, along with their line numbers.
+    pending: VecDeque<(usize, String)>,
+    started: bool,
+}
+
+impl ContentsReader<BufReader<IndexReader>> {
+    /// Opens a Contents file, which is decompressed according to its extension.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        IndexReader::open(path).map(|file| ContentsReader::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead> ContentsReader<R> {
+    pub fn new(reader: R) -> Self {
+        ContentsReader {
+            reader,
+            line: 0,
+            pending: VecDeque::new(),
+            started: false,
+        }
+    }
+
+    /// Finds the entry for the given file, such as `/usr/bin/foo`, reading no further than it.
+    pub fn find_path(self, path: &str) -> io::Result<Option<ContentsEntry>> {
+        let path = path.trim_start_matches('/');
+
+        for entry in self {
+            let entry = entry?;
+            if entry.path == path {
+                return Ok(Some(entry));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<(usize, String)>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        self.line += 1;
+        Ok(Some((self.line, line)))
+    }
+
+    // Holds back the first lines of the file until it is known whether they are a header.
+    fn skip_header(&mut self) -> io::Result<()> {
+        while self.pending.len() < HEADER_LIMIT {
+            let (number, line) = match self.read_line()? {
+                Some(line) => line,
+                None => break,
+            };
+
+            let mut words = line.split_whitespace();
+            if words.next() == Some("FILE") && words.next() == Some("LOCATION") {
+                self.pending.clear();
+                return Ok(());
+            }
+
+            self.pending.push_back((number, line));
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for ContentsReader<R> {
+    type Item = io::Result<ContentsEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if let Err(why) = self.skip_header() {
+                return Some(Err(why));
+            }
+        }
+
+        loop {
+            let (number, line) = match self.pending.pop_front() {
+                Some(line) => line,
+                None => match self.read_line() {
+                    Ok(Some(line)) => line,
+                    Ok(None) => return None,
+                    Err(why) => return Some(Err(why)),
+                },
+            };
+
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+
+            return Some(parse_line(number, line).map_err(io::Error::from));
+        }
+    }
+}
+
+// The location is the last column, and the path may itself contain spaces.
+fn parse_line(number: usize, line: &str) -> Result<ContentsEntry, ParseError> {
+    let pos = match line.rfind(char::is_whitespace) {
+        Some(pos) => pos,
+        None => {
+            return Err(ParseError::InvalidValue {
+                position: Position::new(number, line, 0..line.len()),
+                reason: format!("missing the packages of a file: {}", line),
+            })
+        }
+    };
+
+    Ok(ContentsEntry {
+        path: line[..pos].trim_end().trim_start_matches('/').to_owned(),
+        packages: line[pos + 1..].split(',').map(String::from).collect(),
+    })
+}
@@ -0,0 +1,121 @@
+use super::error::span;
+use super::{HashAlgorithm, ParseError, Position, ReleaseEntry};
+use std::ops::Range;
+
+// A field of a deb822 control paragraph, such as a stanza of a Packages index.
+pub(crate) struct Field<'a> {
+    pub key: &'a str,
+    // Continuation lines are joined by newlines, without their leading space.
+    pub value: String,
+    // The 1-based line number and the text of the line which defined the field.
+    pub line: usize,
+    pub text: &'a str,
+    // The byte range of the value within the first line.
+    pub span: Range<usize>,
+}
+
+impl<'a> Field<'a> {
+    pub fn position(&self) -> Position {
+        Position::new(self.line, self.text, self.span.clone())
+    }
+
+    // Parses the hash, size, and name on each continuation line of a multi-line checksum field.
+    pub fn rows(&self, algorithm: &HashAlgorithm) -> Result<Vec<ReleaseEntry>, ParseError> {
+        self.value
+            .split('\n')
+            .enumerate()
+            .filter(|&(_, row)| !row.trim().is_empty())
+            .map(|(offset, row)| ReleaseEntry::parse_row(algorithm, self.line + offset, row))
+            .collect()
+    }
+}
+
+// The fields of a paragraph, along with the line number where it begins.
+pub(crate) struct Paragraph<'a> {
+    pub line: usize,
+    pub fields: Vec<Field<'a>>,
+}
+
+impl<'a> Paragraph<'a> {
+    pub fn get(&self, key: &str) -> Option<&Field<'a>> {
+        self.fields.iter().find(|field| field.key == key)
+    }
+
+    pub fn missing(&self, field: &str) -> ParseError {
+        let text = self.fields.first().map_or("", |field| field.text);
+        ParseError::MissingField {
+            position: Position::new(self.line, text, 0..text.len()),
+            field: field.to_owned(),
+        }
+    }
+}
+
+// Splits the input into paragraphs which are separated by blank lines.
+pub(crate) fn paragraphs(input: &str) -> Result<Vec<Paragraph<'_>>, ParseError> {
+    let mut paragraphs = Vec::new();
+    let mut current: Option<Paragraph> = None;
+
+    for (number, line) in input.lines().enumerate() {
+        let number = number + 1;
+
+        if line.trim().is_empty() {
+            paragraphs.extend(current.take());
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with(' ') || line.starts_with('\t') {
+            match current
+                .as_mut()
+                .and_then(|paragraph| paragraph.fields.last_mut())
+            {
+                Some(field) => {
+                    field.value.push('\n');
+                    field.value.push_str(&line[1..]);
+                }
+                None => {
+                    return Err(ParseError::UnknownKey {
+                        position: Position::new(number, line, 0..line.len()),
+                    })
+                }
+            }
+
+            continue;
+        }
+
+        let (key, value) = match line.find(':') {
+            Some(pos) => (&line[..pos], line[pos + 1..].trim()),
+            None => {
+                return Err(ParseError::UnknownKey {
+                    position: Position::new(number, line, 0..line.len()),
+                })
+            }
+        };
+
+        let paragraph = current.get_or_insert_with(|| Paragraph {
+            line: number,
+            fields: Vec::new(),
+        });
+
+        if paragraph.get(key).is_some() {
+            return Err(ParseError::DuplicateField {
+                position: Position::new(number, line, 0..key.len()),
+                field: key.to_owned(),
+            });
+        }
+
+        paragraph.fields.push(Field {
+            key,
+            value: value.to_owned(),
+            line: number,
+            text: line,
+            span: span(line, value),
+        });
+    }
+
+    paragraphs.extend(current);
+    Ok(paragraphs)
+}
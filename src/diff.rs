@@ -0,0 +1,227 @@
+use super::{is_known_field, DistRelease, EntryVariant, ReleaseFile};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The differences between two versions of a release file, such as before and after a suite
+/// was republished.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReleaseDiff {
+    /// Header fields which were changed or removed, in the order that the old release file
+    /// writes them, followed by those which were added. Known fields are compared by their
+    /// values rather than their text, so that dates, lists, and booleans which are only
+    /// written differently are unchanged.
+    pub fields: Vec<FieldChange>,
+    /// Files which were added, removed, or changed, grouped by their component, and then by
+    /// the uncompressed variant of their index, so that every compressed form of an index is
+    /// in the same group. Files in the base of the `dists/<suite>/` directory have no
+    /// component, and files whose variant is not recognized are grouped under `None`. Groups
+    /// are ordered by their first path, and the files of each group are sorted by path.
+    pub components: BTreeMap<Option<String>, Vec<IndexChanges>>,
+}
+
+/// The uncompressed variant of an index, if it is recognized, and the changes to its files.
+pub type IndexChanges = (Option<EntryVariant>, Vec<FileChange>);
+
+impl ReleaseDiff {
+    /// Whether both release files are the same.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.components.is_empty()
+    }
+
+    /// Every file that was added or removed, or changed.
+    pub fn files(&self) -> impl Iterator<Item = &FileChange> {
+        self.components
+            .values()
+            .flat_map(|indices| indices.iter())
+            .flat_map(|(_, files)| files.iter())
+    }
+
+    /// The files of the new release file which were added or changed, and so need to be
+    /// downloaded again.
+    pub fn updated_files(&self) -> impl Iterator<Item = &ReleaseFile> {
+        self.files().filter_map(|change| match *change {
+            FileChange::Added(ref file) | FileChange::Changed { new: ref file, .. } => Some(file),
+            FileChange::Removed(_) => None,
+        })
+    }
+
+    /// The indices which were added, removed, or changed, as the variants of their
+    /// uncompressed forms.
+    ///
+    /// Each index is listed once, regardless of how many of its compressed forms changed.
+    pub fn indices(&self) -> Vec<EntryVariant> {
+        let mut indices: Vec<EntryVariant> = Vec::new();
+
+        for variant in self
+            .components
+            .values()
+            .flatten()
+            .filter_map(|(variant, _)| variant.as_ref())
+        {
+            if !indices.contains(variant) {
+                indices.push(variant.clone());
+            }
+        }
+
+        indices
+    }
+}
+
+impl fmt::Display for ReleaseDiff {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for field in &self.fields {
+            if let Some(ref old) = field.old {
+                writeln!(fmt, "- {}: {}", field.key, old)?;
+            }
+
+            if let Some(ref new) = field.new {
+                writeln!(fmt, "+ {}: {}", field.key, new)?;
+            }
+        }
+
+        for change in self.files() {
+            let sign = match *change {
+                FileChange::Added(_) => '+',
+                FileChange::Removed(_) => '-',
+                FileChange::Changed { .. } => '~',
+            };
+
+            writeln!(fmt, "{} {}", sign, change.path())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A header field whose value differs between two release files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub key: String,
+    /// The value in the old release file, as it is written, if it was defined.
+    pub old: Option<String>,
+    /// The value in the new release file, as it is written, if it is defined.
+    pub new: Option<String>,
+}
+
+/// A file whose entries differ between two release files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileChange {
+    Added(ReleaseFile),
+    Removed(ReleaseFile),
+    /// The size of the file, or its sum for a checksum section that both release files list,
+    /// has changed.
+    Changed {
+        old: ReleaseFile,
+        new: ReleaseFile,
+    },
+}
+
+impl FileChange {
+    /// The file as it is listed by the new release file, or by the old one if it was removed.
+    pub fn file(&self) -> &ReleaseFile {
+        match *self {
+            FileChange::Added(ref file)
+            | FileChange::Removed(ref file)
+            | FileChange::Changed { new: ref file, .. } => file,
+        }
+    }
+
+    /// The path of the file, relative to the `dists/<suite>/` directory.
+    pub fn path(&self) -> &str {
+        &self.file().path
+    }
+
+    /// The precise variant of the file, if it is one that this crate recognizes.
+    pub fn variant(&self) -> Option<EntryVariant> {
+        self.file().variant()
+    }
+}
+
+impl DistRelease {
+    /// Compares the header fields and files of two release files.
+    pub fn diff(old: &DistRelease, new: &DistRelease) -> ReleaseDiff {
+        let mut diff = ReleaseDiff::default();
+
+        let (old_fields, new_fields) = (old.header_fields(), new.header_fields());
+        let value = |fields: &[(&str, String)], key: &str| {
+            fields
+                .iter()
+                .find(|&&(other, _)| other == key)
+                .map(|(_, value)| value.clone())
+        };
+
+        let keys = old_fields.iter().chain(
+            new_fields
+                .iter()
+                .filter(|&&(key, _)| value(&old_fields, key).is_none()),
+        );
+
+        // Known fields are compared by their typed values, so that a date written with `+0000`
+        // is the same as one written with `UTC`. Others may only be compared as text.
+        let (old_typed, new_typed) = (old.typed_fields(), new.typed_fields());
+
+        for &(key, _) in keys {
+            let (old, new) = (value(&old_fields, key), value(&new_fields, key));
+            let changed = if is_known_field(key) {
+                value(&old_typed, key) != value(&new_typed, key)
+            } else {
+                old != new
+            };
+
+            if changed {
+                diff.fields.push(FieldChange {
+                    key: key.to_owned(),
+                    old,
+                    new,
+                });
+            }
+        }
+
+        let mut changes: BTreeMap<String, FileChange> = BTreeMap::new();
+        let mut new_files = new
+            .files()
+            .into_iter()
+            .map(|file| (file.path.clone(), file))
+            .collect::<BTreeMap<String, ReleaseFile>>();
+
+        for file in old.files() {
+            match new_files.remove(&file.path) {
+                Some(new) if !is_changed(&file, &new) => (),
+                Some(new) => {
+                    changes.insert(file.path.clone(), FileChange::Changed { old: file, new });
+                }
+                None => {
+                    changes.insert(file.path.clone(), FileChange::Removed(file));
+                }
+            }
+        }
+
+        for (path, file) in new_files {
+            changes.insert(path, FileChange::Added(file));
+        }
+
+        for change in changes.into_values() {
+            let component = change.file().component().map(String::from);
+            let variant = change.variant().map(|variant| variant.uncompressed());
+            let indices = diff.components.entry(component).or_default();
+
+            match indices.iter_mut().find(|(other, _)| *other == variant) {
+                Some((_, files)) => files.push(change),
+                None => indices.push((variant, vec![change])),
+            }
+        }
+
+        diff
+    }
+}
+
+// Sums are only compared for the checksum sections that both files list, so that dropping a
+// weak checksum section does not mark every file as changed.
+fn is_changed(old: &ReleaseFile, new: &ReleaseFile) -> bool {
+    old.size != new.size
+        || old.sums.iter().any(|(algorithm, sum)| {
+            new.sums
+                .get(algorithm)
+                .is_some_and(|other| !other.eq_ignore_ascii_case(sum))
+        })
+}
@@ -1,102 +1,422 @@
-use super::ImageSize;
+use super::hash::decode_hex;
+use super::{
+    ChecksumError, Compression, Digest, HashAlgorithm, ImageSize, IndexReader, ParseError,
+    ReleaseEntryRef,
+};
 use deb_architectures::Architecture;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::num::ParseIntError;
+use std::path::Path;
 use std::str::FromStr;
+use std::{fmt, io};
 
 /// The hash, size, and path of a file that this release file points to.
+///
+/// The sum is hex-encoded text by default, as it is written. Entries may instead store the
+/// decoded bytes of their sums as a `ReleaseEntry<Digest>`.
 #[derive(Debug, Default, Clone, Hash, PartialEq)]
-pub struct ReleaseEntry {
-    pub sum: String,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ReleaseEntry<S = String> {
+    pub sum: S,
     pub size: u64,
     pub path: String,
 }
 
-impl ReleaseEntry {
+impl<S> ReleaseEntry<S> {
     /// If required, the precise variant of an apt entry can be determined here.
     ///
     /// Malformed / unsupported apt entries will return `None`.
     pub fn variant(&self) -> Option<EntryVariant> {
         entry_variant(&self.path)
     }
+
+    /// The compression of the file, according to its variant or the extension of its path.
+    pub fn compression(&self) -> Compression {
+        self.variant()
+            .and_then(|variant| variant.compression().cloned())
+            .unwrap_or_else(|| Compression::from_path(&self.path))
+    }
+
+    /// Opens the file within the `dists/<suite>/` directory, reading its plain content.
+    pub fn open<P: AsRef<Path>>(&self, dir: P) -> io::Result<IndexReader> {
+        IndexReader::with_compression(dir.as_ref().join(&self.path), self.compression())
+    }
+}
+
+impl ReleaseEntry {
+    /// Decodes the hex-encoded sum into the bytes of its digest.
+    pub fn decode_sum(&self) -> Result<Vec<u8>, ChecksumError> {
+        decode_hex(&self.sum)
+    }
+
+    /// Validates the sum for the algorithm of its checksum section, and stores it as the bytes
+    /// of its digest.
+    pub fn decode(&self, algorithm: &HashAlgorithm) -> Result<ReleaseEntry<Digest>, ChecksumError> {
+        Ok(ReleaseEntry {
+            sum: Digest::decode(algorithm, &self.sum)?,
+            size: self.size,
+            path: self.path.clone(),
+        })
+    }
+
+    // Parses a checksum row of the given section, such as those of a release file or the `Files`
+    // field of a Sources index.
+    pub(crate) fn parse_row(
+        algorithm: &HashAlgorithm,
+        number: usize,
+        line: &str,
+    ) -> Result<Self, ParseError> {
+        ReleaseEntryRef::parse_row(algorithm, number, line).map(ReleaseEntryRef::into_owned)
+    }
+}
+
+impl From<ReleaseEntry<Digest>> for ReleaseEntry {
+    fn from(entry: ReleaseEntry<Digest>) -> Self {
+        ReleaseEntry {
+            sum: entry.sum.to_string(),
+            size: entry.size,
+            path: entry.path,
+        }
+    }
 }
 
 impl FromStr for ReleaseEntry {
-    type Err = &'static str;
+    type Err = EntryError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let mut iterator = input.split_whitespace();
+        ReleaseEntryRef::parse(input).map(ReleaseEntryRef::into_owned)
+    }
+}
 
-        let output = Self {
-            sum: iterator.next().ok_or("missing sum field")?.to_owned(),
-            size: iterator
-                .next()
-                .ok_or("missing size field")?
-                .parse::<u64>()
-                .map_err(|_| "size field is not a number")?,
-            path: iterator.next().ok_or("missing path field")?.to_owned(),
-        };
+/// Reasons that a checksum entry may fail to parse.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryError {
+    MissingSum,
+    MissingSize,
+    MissingPath,
+    InvalidSize(ParseIntError),
+}
 
-        Ok(output)
+impl fmt::Display for EntryError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EntryError::MissingSum => fmt.write_str("missing sum field"),
+            EntryError::MissingSize => fmt.write_str("missing size field"),
+            EntryError::MissingPath => fmt.write_str("missing path field"),
+            EntryError::InvalidSize(ref why) => write!(fmt, "size field is not a number: {}", why),
+        }
+    }
+}
+
+impl Error for EntryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            EntryError::InvalidSize(ref why) => Some(why),
+            _ => None,
+        }
     }
 }
 
 /// Defines the kind of file that this apt entry is.
 #[derive(Debug, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum EntryVariant {
-    Binary(BinaryEntry, Architecture),
-    Contents(Architecture, Option<String>),
+    Binary(
+        BinaryEntry,
+        #[cfg_attr(feature = "serde", serde(with = "::serialize::architecture"))] Architecture,
+    ),
+    Contents(
+        #[cfg_attr(feature = "serde", serde(with = "::serialize::architecture"))] Architecture,
+        Compression,
+    ),
+    /// Contents of a single component, such as `main/Contents-amd64.gz`.
+    ComponentContents(
+        String,
+        #[cfg_attr(feature = "serde", serde(with = "::serialize::architecture"))] Architecture,
+        Compression,
+    ),
+    /// Command-not-found metadata of a component, such as `main/cnf/Commands-amd64.xz`.
+    Commands(
+        String,
+        #[cfg_attr(feature = "serde", serde(with = "::serialize::architecture"))] Architecture,
+        Compression,
+    ),
+    /// An object of an `Acquire-By-Hash` repository, named after its digest.
+    ByHash(ByHashEntry),
+    DebianInstaller(DebianInstallerEntry),
+    /// The `.diff/Index` of an index which may be updated with patches, such as
+    /// `main/binary-amd64/Packages.diff/Index`. The variant of the uncompressed index is kept.
+    Pdiff(Box<EntryVariant>),
     Dep11(Dep11Entry),
     Source(SourceEntry),
     I18n(I18nEntry),
 }
 
+impl EntryVariant {
+    /// The compression of the file, if the variant is one that may be compressed.
+    pub fn compression(&self) -> Option<&Compression> {
+        match *self {
+            EntryVariant::Binary(BinaryEntry::Packages(ref compression), _)
+            | EntryVariant::Contents(_, ref compression)
+            | EntryVariant::ComponentContents(_, _, ref compression)
+            | EntryVariant::Commands(_, _, ref compression)
+            | EntryVariant::DebianInstaller(DebianInstallerEntry::Binary(
+                BinaryEntry::Packages(ref compression),
+                _,
+            ))
+            | EntryVariant::DebianInstaller(DebianInstallerEntry::Contents(
+                _,
+                _,
+                ref compression,
+            ))
+            | EntryVariant::Dep11(Dep11Entry::Components(_, ref compression))
+            | EntryVariant::Dep11(Dep11Entry::Icons(_, ref compression))
+            | EntryVariant::Source(SourceEntry::Sources(ref compression))
+            | EntryVariant::I18n(I18nEntry::Translations(_, ref compression)) => Some(compression),
+            EntryVariant::Binary(BinaryEntry::Release, _)
+            | EntryVariant::ByHash(_)
+            | EntryVariant::Pdiff(_)
+            | EntryVariant::DebianInstaller(DebianInstallerEntry::Binary(
+                BinaryEntry::Release,
+                _,
+            ))
+            | EntryVariant::Source(SourceEntry::Release)
+            | EntryVariant::I18n(I18nEntry::Index) => None,
+        }
+    }
+
+    /// The architecture of the index, if the variant is specific to one.
+    pub fn architecture(&self) -> Option<Architecture> {
+        match *self {
+            EntryVariant::Binary(_, arch)
+            | EntryVariant::Contents(arch, _)
+            | EntryVariant::ComponentContents(_, arch, _)
+            | EntryVariant::Commands(_, arch, _)
+            | EntryVariant::DebianInstaller(DebianInstallerEntry::Binary(_, arch))
+            | EntryVariant::DebianInstaller(DebianInstallerEntry::Contents(_, arch, _))
+            | EntryVariant::Dep11(Dep11Entry::Components(arch, _)) => Some(arch),
+            EntryVariant::Pdiff(ref variant) => variant.architecture(),
+            EntryVariant::ByHash(_)
+            | EntryVariant::Dep11(Dep11Entry::Icons(..))
+            | EntryVariant::Source(_)
+            | EntryVariant::I18n(_) => None,
+        }
+    }
+
+    /// The variant of the uncompressed form of the same index.
+    ///
+    /// Every compressed form of an index has the same uncompressed variant.
+    pub fn uncompressed(&self) -> EntryVariant {
+        let mut variant = self.clone();
+
+        match variant {
+            EntryVariant::Binary(BinaryEntry::Packages(ref mut compression), _)
+            | EntryVariant::Contents(_, ref mut compression)
+            | EntryVariant::ComponentContents(_, _, ref mut compression)
+            | EntryVariant::Commands(_, _, ref mut compression)
+            | EntryVariant::DebianInstaller(DebianInstallerEntry::Binary(
+                BinaryEntry::Packages(ref mut compression),
+                _,
+            ))
+            | EntryVariant::DebianInstaller(DebianInstallerEntry::Contents(
+                _,
+                _,
+                ref mut compression,
+            ))
+            | EntryVariant::Dep11(Dep11Entry::Components(_, ref mut compression))
+            | EntryVariant::Dep11(Dep11Entry::Icons(_, ref mut compression))
+            | EntryVariant::Source(SourceEntry::Sources(ref mut compression))
+            | EntryVariant::I18n(I18nEntry::Translations(_, ref mut compression)) => {
+                *compression = Compression::None
+            }
+            _ => (),
+        }
+
+        variant
+    }
+
+    /// Whether both variants are forms of the same index, regardless of their compression.
+    pub fn is_same_index(&self, other: &EntryVariant) -> bool {
+        self.uncompressed() == other.uncompressed()
+    }
+
+    /// Picks the form of this index which apt would prefer to download from the given entries.
+    ///
+    /// Entries of other indices, and forms which this crate is unable to decompress, are skipped.
+    pub fn best_entry<'a, I>(&self, entries: I) -> Option<&'a ReleaseEntry>
+    where
+        I: IntoIterator<Item = &'a ReleaseEntry>,
+    {
+        entries
+            .into_iter()
+            .filter_map(|entry| {
+                let variant = entry.variant()?;
+                if !self.is_same_index(&variant) {
+                    return None;
+                }
+
+                let compression = variant.compression().cloned().unwrap_or(Compression::None);
+                compression.preference().map(|rank| (rank, entry))
+            })
+            .min_by_key(|&(rank, _)| rank)
+            .map(|(_, entry)| entry)
+    }
+}
+
+/// The location of an object within the `by-hash` directory of an index.
+#[derive(Debug, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ByHashEntry {
+    pub component: Option<String>,
+    /// The directory of the index within its component, such as `binary-amd64`.
+    pub directory: String,
+    pub algorithm: HashAlgorithm,
+    pub digest: String,
+}
+
+/// Debian installer entries contain the indices of udebs, the packages used by the installer.
+#[derive(Debug, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DebianInstallerEntry {
+    Binary(
+        BinaryEntry,
+        #[cfg_attr(feature = "serde", serde(with = "::serialize::architecture"))] Architecture,
+    ),
+    /// Contents of udebs, which are listed both at the top level, where the component is
+    /// `None`, and within each component, such as `main/Contents-udeb-amd64.gz`.
+    Contents(
+        Option<String>,
+        #[cfg_attr(feature = "serde", serde(with = "::serialize::architecture"))] Architecture,
+        Compression,
+    ),
+}
+
 /// Dep11 entries contain appstream metadata and their required icons.
 #[derive(Debug, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Dep11Entry {
-    Components(Architecture, Option<String>),
-    Icons(ImageSize, Option<String>),
+    Components(
+        #[cfg_attr(feature = "serde", serde(with = "::serialize::architecture"))] Architecture,
+        Compression,
+    ),
+    Icons(ImageSize, Compression),
 }
 
 /// I18n entries contain translations for a given locale.
 #[derive(Debug, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum I18nEntry {
     Index,
-    Translations(String, Option<String>),
+    Translations(String, Compression),
 }
 
 /// Binary entries contain the Packages lists, which dpkg and apt use for dependency resolution.
 #[derive(Debug, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum BinaryEntry {
-    Packages(Option<String>),
+    Packages(Compression),
     Release,
 }
 
 /// Similar to binary entries, but for source packages.
 #[derive(Debug, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum SourceEntry {
-    Sources(Option<String>),
+    Sources(Compression),
     Release,
 }
 
 // If the apt entry is not a base length, it has an extension.
-fn extension_from(input: &str, len: usize) -> Option<String> {
+fn extension_from(input: &str, len: usize) -> Compression {
     if input.len() < len + 1 {
-        None
+        Compression::None
     } else {
-        Some(input[len + 1..].to_owned())
+        Compression::from_extension(&input[len + 1..])
     }
 }
 
-// Apt entries tend to name a variant with a possible extension (compression).
-fn type_with_extension<T: FromStr>(input: &str) -> Option<(T, Option<String>)> {
+// Apt entries tend to name a variant with a possible extension (compression). Some also carry
+// the extension of their format, such as `yml` or `tar`, before the compression.
+fn type_with_extension<T: FromStr>(input: &str, format: &str) -> Option<(T, Compression)> {
     let (kind, ext) = match input.find('.') {
-        Some(pos) => (&input[..pos], Some(input[pos + 1..].to_owned())),
-        None => (input, None),
+        Some(pos) => (&input[..pos], &input[pos + 1..]),
+        None => (input, ""),
+    };
+
+    let ext = match ext.strip_prefix(format) {
+        Some(rest) if !format.is_empty() => rest.trim_start_matches('.'),
+        _ => ext,
+    };
+
+    kind.parse::<T>()
+        .ok()
+        .map(|kind| (kind, Compression::from_extension(ext)))
+}
+
+// Packages and Release files within a `binary-<arch>/` directory.
+fn binary_variant(binary: &str) -> Option<(BinaryEntry, Architecture)> {
+    binary.find('/').and_then(|pos| {
+        binary[..pos].parse::<Architecture>().ok().and_then(|arch| {
+            let filename = &binary[pos + 1..];
+            if filename.starts_with("Packages") {
+                Some((BinaryEntry::Packages(extension_from(filename, 8)), arch))
+            } else if filename.starts_with("Release") {
+                Some((BinaryEntry::Release, arch))
+            } else {
+                None
+            }
+        })
+    })
+}
+
+// Objects of `Acquire-By-Hash` repositories, such as `main/binary-amd64/by-hash/SHA256/<digest>`.
+fn by_hash_variant(path: &str) -> Option<ByHashEntry> {
+    let segments = path.split('/').collect::<Vec<&str>>();
+    let pos = segments.iter().position(|&segment| segment == "by-hash")?;
+
+    let (algorithm, digest) = match segments[pos + 1..] {
+        [algorithm, digest] if !digest.is_empty() => (algorithm, digest),
+        _ => return None,
+    };
+
+    let (component, directory) = match segments[..pos].split_first() {
+        Some((component, directory)) => (Some((*component).to_owned()), directory.join("/")),
+        None => (None, String::new()),
     };
 
-    kind.parse::<T>().ok().map(|kind| (kind, ext))
+    Some(ByHashEntry {
+        component,
+        directory,
+        algorithm: HashAlgorithm::from(algorithm),
+        digest: digest.to_owned(),
+    })
 }
 
-fn entry_variant(original_path: &str) -> Option<EntryVariant> {
+pub(crate) fn entry_variant(original_path: &str) -> Option<EntryVariant> {
+    if let Some(entry) = by_hash_variant(original_path) {
+        return Some(EntryVariant::ByHash(entry));
+    }
+
+    if let Some(index) = original_path.strip_suffix(".diff/Index") {
+        return entry_variant(index).map(|variant| EntryVariant::Pdiff(Box::new(variant)));
+    }
+
+    // The component that precedes what remains of the path.
+    let component = |path: &str| {
+        original_path[..original_path.len() - path.len()]
+            .trim_end_matches('/')
+            .to_owned()
+    };
+
     let mut path = original_path;
     let mut found = false;
     while let Some(pos) = path.find('/') {
@@ -105,33 +425,30 @@ fn entry_variant(original_path: &str) -> Option<EntryVariant> {
 
         match base {
             _ if base.starts_with("binary-") => {
-                let binary = &path[7..];
-
-                return binary.find('/').and_then(|pos| {
-                    binary[..pos].parse::<Architecture>().ok().and_then(|arch| {
-                        let filename = &binary[pos + 1..];
-                        if filename.starts_with("Packages") {
-                            let ext = extension_from(filename, 8);
-                            Some(EntryVariant::Binary(BinaryEntry::Packages(ext), arch))
-                        } else if filename.starts_with("Release") {
-                            Some(EntryVariant::Binary(BinaryEntry::Release, arch))
-                        } else {
-                            None
-                        }
-                    })
-                });
+                return binary_variant(&path[7..])
+                    .map(|(entry, arch)| EntryVariant::Binary(entry, arch));
+            }
+            "cnf" => {
+                return path[pos + 1..]
+                    .strip_prefix("Commands-")
+                    .and_then(|rest| type_with_extension::<Architecture>(rest, ""))
+                    .map(|(arch, ext)| EntryVariant::Commands(component(path), arch, ext));
             }
             "debian-installer" => {
-                return None;
-                // TODO
+                return path[pos + 1..]
+                    .strip_prefix("binary-")
+                    .and_then(binary_variant)
+                    .map(|(entry, arch)| {
+                        EntryVariant::DebianInstaller(DebianInstallerEntry::Binary(entry, arch))
+                    });
             }
             "dep11" => {
                 let path = &path[6..];
-                return if path.starts_with("icons-") {
-                    type_with_extension::<ImageSize>(&path[6..])
+                return if let Some(path) = path.strip_prefix("icons-") {
+                    type_with_extension::<ImageSize>(path, "tar")
                         .map(|(res, ext)| EntryVariant::Dep11(Dep11Entry::Icons(res, ext)))
-                } else if path.starts_with("Components-") {
-                    type_with_extension::<Architecture>(&path[11..])
+                } else if let Some(path) = path.strip_prefix("Components-") {
+                    type_with_extension::<Architecture>(path, "yml")
                         .map(|(arch, ext)| EntryVariant::Dep11(Dep11Entry::Components(arch, ext)))
                 } else {
                     None
@@ -140,7 +457,7 @@ fn entry_variant(original_path: &str) -> Option<EntryVariant> {
             "i18n" => {
                 let path = &path[5..];
                 return if path.starts_with("Translation") {
-                    type_with_extension::<String>(&path[12..])
+                    type_with_extension::<String>(&path[12..], "")
                         .map(|(loc, ext)| EntryVariant::I18n(I18nEntry::Translations(loc, ext)))
                 } else if path == "Index" {
                     Some(EntryVariant::I18n(I18nEntry::Index))
@@ -163,9 +480,22 @@ fn entry_variant(original_path: &str) -> Option<EntryVariant> {
         }
     }
 
-    if !found && original_path.starts_with("Contents-") {
-        return type_with_extension::<Architecture>(&original_path[9..])
-            .map(|(arch, ext)| EntryVariant::Contents(arch, ext));
+    // Contents of udebs are listed at both the top level and within each component.
+    if let Some(rest) = path.strip_prefix("Contents-udeb-") {
+        return type_with_extension::<Architecture>(rest, "").map(|(arch, ext)| {
+            let component = if found { Some(component(path)) } else { None };
+            EntryVariant::DebianInstaller(DebianInstallerEntry::Contents(component, arch, ext))
+        });
+    }
+
+    if let Some(rest) = path.strip_prefix("Contents-") {
+        return type_with_extension::<Architecture>(rest, "").map(|(arch, ext)| {
+            if found {
+                EntryVariant::ComponentContents(component(path), arch, ext)
+            } else {
+                EntryVariant::Contents(arch, ext)
+            }
+        });
     }
 
     None
@@ -179,10 +509,7 @@ mod tests {
     fn entry_parsing() {
         assert_eq!(
             entry_variant("binary-amd64/Packages.xz").expect("bad entry result"),
-            EntryVariant::Binary(
-                BinaryEntry::Packages(Some("xz".into())),
-                Architecture::Amd64
-            )
+            EntryVariant::Binary(BinaryEntry::Packages(Compression::Xz), Architecture::Amd64)
         )
     }
 }
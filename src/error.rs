@@ -0,0 +1,168 @@
+use super::{ChecksumError, EntryError};
+use std::error::Error;
+use std::ops::Range;
+use std::{fmt, io};
+
+/// Where in the input that a parsing error occurred.
+#[derive(Debug, Clone, Hash, PartialEq)]
+pub struct Position {
+    /// The 1-based line number of the offending line.
+    pub line: usize,
+    /// The text of the offending line.
+    pub text: String,
+    /// The byte range within the line which caused the error.
+    pub span: Range<usize>,
+}
+
+impl Position {
+    pub(crate) fn new(line: usize, text: &str, span: Range<usize>) -> Self {
+        Position {
+            line,
+            text: text.to_owned(),
+            span,
+        }
+    }
+
+    /// The offending text within the line.
+    pub fn fragment(&self) -> &str {
+        self.text.get(self.span.clone()).unwrap_or(&self.text)
+    }
+}
+
+// The byte range of a slice within the line that it was sliced from.
+pub(crate) fn span(line: &str, part: &str) -> Range<usize> {
+    let start = part.as_ptr() as usize - line.as_ptr() as usize;
+    start..start + part.len()
+}
+
+/// An error that occurred while parsing a release file or one of the indices it points to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// A checksum entry was defined before any checksum section.
+    EntryOutsideSection { position: Position },
+    /// A field or checksum section was defined more than once.
+    DuplicateField { position: Position, field: String },
+    /// The sum of a checksum entry is not valid for its checksum section.
+    InvalidChecksum {
+        position: Position,
+        reason: ChecksumError,
+    },
+    /// A date field could not be parsed.
+    InvalidDate { position: Position, reason: String },
+    /// A checksum entry was missing one of its fields.
+    InvalidEntry {
+        position: Position,
+        reason: EntryError,
+    },
+    /// The size of a checksum entry was not a number.
+    InvalidSize { position: Position },
+    /// A stanza of an index did not define one of its required fields.
+    MissingField { position: Position, field: String },
+    /// A field contained a value that it does not support.
+    InvalidValue { position: Position, reason: String },
+    /// A line which is neither a field, a checksum section, nor a checksum entry.
+    ///
+    /// In an index, a line which is neither a field nor the continuation of one.
+    UnknownKey { position: Position },
+}
+
+impl ParseError {
+    pub fn position(&self) -> &Position {
+        match *self {
+            ParseError::EntryOutsideSection { ref position }
+            | ParseError::DuplicateField { ref position, .. }
+            | ParseError::InvalidChecksum { ref position, .. }
+            | ParseError::InvalidDate { ref position, .. }
+            | ParseError::InvalidEntry { ref position, .. }
+            | ParseError::InvalidSize { ref position }
+            | ParseError::MissingField { ref position, .. }
+            | ParseError::InvalidValue { ref position, .. }
+            | ParseError::UnknownKey { ref position } => position,
+        }
+    }
+
+    /// The 1-based line number where the error occurred.
+    pub fn line(&self) -> usize {
+        self.position().line
+    }
+
+    // Shifts the line number when the parsed text is embedded in a larger file.
+    pub(crate) fn offset(mut self, lines: usize) -> Self {
+        match self {
+            ParseError::EntryOutsideSection { ref mut position }
+            | ParseError::DuplicateField {
+                ref mut position, ..
+            }
+            | ParseError::InvalidChecksum {
+                ref mut position, ..
+            }
+            | ParseError::InvalidDate {
+                ref mut position, ..
+            }
+            | ParseError::InvalidEntry {
+                ref mut position, ..
+            }
+            | ParseError::InvalidSize { ref mut position }
+            | ParseError::MissingField {
+                ref mut position, ..
+            }
+            | ParseError::InvalidValue {
+                ref mut position, ..
+            }
+            | ParseError::UnknownKey { ref mut position } => position.line += lines,
+        }
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "line {}: ", self.line())?;
+        match *self {
+            ParseError::EntryOutsideSection { ref position } => write!(
+                fmt,
+                "checksum entry outside of a checksum section: {}",
+                position.text
+            ),
+            ParseError::DuplicateField { ref field, .. } => {
+                write!(fmt, "{} is defined more than once", field)
+            }
+            ParseError::InvalidChecksum {
+                ref position,
+                ref reason,
+            } => write!(
+                fmt,
+                "invalid checksum ({}): {}",
+                reason,
+                position.fragment()
+            ),
+            ParseError::InvalidDate { ref reason, .. } => write!(fmt, "invalid date: {}", reason),
+            ParseError::InvalidEntry {
+                ref position,
+                ref reason,
+            } => write!(
+                fmt,
+                "invalid checksum entry ({}): {}",
+                reason, position.text
+            ),
+            ParseError::InvalidSize { ref position } => {
+                write!(fmt, "size is not a number: {}", position.fragment())
+            }
+            ParseError::MissingField { ref field, .. } => {
+                write!(fmt, "stanza is missing the {} field", field)
+            }
+            ParseError::InvalidValue { ref reason, .. } => write!(fmt, "{}", reason),
+            ParseError::UnknownKey { ref position } => {
+                write!(fmt, "line is not a field: {}", position.text)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl From<ParseError> for io::Error {
+    fn from(why: ParseError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, why)
+    }
+}
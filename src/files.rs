@@ -0,0 +1,137 @@
+use super::entry::entry_variant;
+use super::{DistRelease, EntryComponents, EntryVariant, HashAlgorithm, ReleaseEntry};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A file listed in the release file, with its checksums from every checksum section.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReleaseFile {
+    /// The path of the file, relative to the `dists/<suite>/` directory.
+    pub path: String,
+    /// The size listed by the strongest checksum section that this crate can compute, or by
+    /// the first section if it can compute none of them.
+    pub size: u64,
+    /// The hex-encoded digest of the file for each checksum section.
+    pub sums: BTreeMap<HashAlgorithm, String>,
+    /// Set when the checksum sections that this crate can compute disagree on the size of the
+    /// file.
+    pub size_mismatch: bool,
+}
+
+impl ReleaseFile {
+    pub(crate) fn new(path: String, entry: &ReleaseEntry) -> Self {
+        ReleaseFile {
+            path,
+            size: entry.size,
+            sums: BTreeMap::new(),
+            size_mismatch: false,
+        }
+    }
+
+    // Checksum sections are ordered from the weakest to the strongest algorithm, so the size
+    // of the strongest section is the one that is kept. Unknown algorithms are ordered last,
+    // but sections which cannot be checked, such as `SHA3-256`, do not decide the size.
+    pub(crate) fn add(&mut self, algorithm: &HashAlgorithm, entry: &ReleaseEntry) {
+        if algorithm.is_known() {
+            if entry.size != self.size && self.sums.keys().any(HashAlgorithm::is_known) {
+                self.size_mismatch = true;
+            }
+
+            self.size = entry.size;
+        }
+
+        self.sums.insert(algorithm.clone(), entry.sum.clone());
+    }
+
+    /// The component that the file belongs to, such as `main`, if it is not in the base of the
+    /// `dists/<suite>/` directory.
+    pub fn component(&self) -> Option<&str> {
+        self.path.find('/').map(|pos| &self.path[..pos])
+    }
+
+    /// The precise variant of the file, if it is one that this crate recognizes.
+    pub fn variant(&self) -> Option<EntryVariant> {
+        entry_variant(&self.path)
+    }
+
+    /// The strongest algorithm that this crate can compute, along with its digest.
+    pub fn strongest_sum(&self) -> Option<(&HashAlgorithm, &str)> {
+        self.sums
+            .iter()
+            .rev()
+            .find(|&(algorithm, _)| algorithm.is_known())
+            .map(|(algorithm, sum)| (algorithm, sum.as_str()))
+    }
+}
+
+impl DistRelease {
+    /// Every file in the release file, sorted by path.
+    pub fn files(&self) -> Vec<ReleaseFile> {
+        let mut files: BTreeMap<String, ReleaseFile> = BTreeMap::new();
+
+        for (algorithm, components) in &self.sums {
+            for (component, entry) in components.iter() {
+                let path = match component {
+                    Some(component) => [component, "/", &entry.path].concat(),
+                    None => entry.path.clone(),
+                };
+
+                files
+                    .entry(path)
+                    .or_insert_with_key(|path| ReleaseFile::new(path.clone(), entry))
+                    .add(algorithm, entry);
+            }
+        }
+
+        files.into_values().collect()
+    }
+
+    /// Looks up a file by its path, relative to the `dists/<suite>/` directory.
+    pub fn get(&self, path: &str) -> Option<ReleaseFile> {
+        let mut file: Option<ReleaseFile> = None;
+
+        for (algorithm, components) in &self.sums {
+            if let Some(entry) = components.get(path) {
+                file.get_or_insert_with(|| ReleaseFile::new(path.to_owned(), entry))
+                    .add(algorithm, entry);
+            }
+        }
+
+        file
+    }
+}
+
+impl EntryComponents {
+    /// Looks up an entry by its path, relative to the `dists/<suite>/` directory.
+    pub fn get(&self, path: &str) -> Option<&ReleaseEntry> {
+        let (entries, path) = self.forms(path)?;
+        entries.iter().find(|entry| entry.path == path)
+    }
+
+    /// Picks the compressed form of an index which apt would prefer to download, given the path
+    /// of its uncompressed form, such as `main/binary-amd64/Packages`.
+    ///
+    /// The returned entry is relative to its component.
+    pub fn best_entry(&self, path: &str) -> Option<&ReleaseEntry> {
+        let (entries, path) = self.forms(path)?;
+        entry_variant(path)?.best_entry(entries)
+    }
+
+    // Every form of the index at the given path, along with the path relative to its component.
+    fn forms<'a, 'b>(&'a self, path: &'b str) -> Option<(&'a Vec<ReleaseEntry>, &'b str)> {
+        let base = match path.find('.') {
+            Some(pos) => &path[..pos],
+            None => path,
+        };
+
+        match path.find('/') {
+            Some(pos) => Some((
+                self.components.get(&path[..pos])?.get(base)?,
+                &path[pos + 1..],
+            )),
+            None => Some((self.base.get(base)?, path)),
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use super::entry::entry_variant;
+use super::hash::Hasher;
+use super::{
+    DebianInstallerEntry, DistRelease, EntryComponents, EntryVariant, HashAlgorithm, ReleaseEntry,
+};
+use chrono::{DateTime, SubsecRound, Utc};
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Generates a release file from the index files in a `dists/<suite>/` directory, in the same
+/// manner as `apt-ftparchive release`.
+///
+/// The `Architectures` and `Components` fields are derived from the index files that were found,
+/// and every index file is hashed with each of the chosen algorithms.
+#[derive(Debug, Clone)]
+pub struct ReleaseBuilder {
+    dir: PathBuf,
+    algorithms: Vec<HashAlgorithm>,
+    release: DistRelease,
+}
+
+impl ReleaseBuilder {
+    /// Creates a builder for the given `dists/<suite>/` directory.
+    ///
+    /// By default, files are hashed with MD5Sum, SHA1, SHA256, and SHA512.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        ReleaseBuilder {
+            dir: dir.into(),
+            algorithms: vec![
+                HashAlgorithm::Md5,
+                HashAlgorithm::Sha1,
+                HashAlgorithm::Sha256,
+                HashAlgorithm::Sha512,
+            ],
+            release: DistRelease::default(),
+        }
+    }
+
+    /// The checksum sections to generate. Unknown algorithms are ignored.
+    pub fn algorithms(mut self, algorithms: &[HashAlgorithm]) -> Self {
+        self.algorithms = algorithms
+            .iter()
+            .filter(|algorithm| algorithm.is_known())
+            .cloned()
+            .collect();
+        self
+    }
+
+    pub fn codename<S: Into<String>>(mut self, codename: S) -> Self {
+        self.release.codename = Some(codename.into());
+        self
+    }
+
+    /// Defaults to the time that the release is built.
+    pub fn date(mut self, date: DateTime<Utc>) -> Self {
+        self.release.date = Some(date);
+        self
+    }
+
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.release.description = Some(description.into());
+        self
+    }
+
+    pub fn label<S: Into<String>>(mut self, label: S) -> Self {
+        self.release.label = Some(label.into());
+        self
+    }
+
+    pub fn origin<S: Into<String>>(mut self, origin: S) -> Self {
+        self.release.origin = Some(origin.into());
+        self
+    }
+
+    pub fn suite<S: Into<String>>(mut self, suite: S) -> Self {
+        self.release.suite = Some(suite.into());
+        self
+    }
+
+    pub fn valid_until(mut self, valid_until: DateTime<Utc>) -> Self {
+        self.release.valid_until = Some(valid_until);
+        self
+    }
+
+    pub fn version<S: Into<String>>(mut self, version: S) -> Self {
+        self.release.version = Some(version.into());
+        self
+    }
+
+    /// Finds and hashes every index file in the directory.
+    pub fn build(self) -> io::Result<DistRelease> {
+        let mut release = self.release;
+
+        let mut paths = Vec::new();
+        collect_indices(&self.dir, String::new(), &mut paths)?;
+
+        let mut architectures = BTreeSet::new();
+        let mut components = BTreeSet::new();
+
+        for path in &paths {
+            if let Some(pos) = path.find('/') {
+                components.insert(path[..pos].to_owned());
+            }
+
+            match entry_variant(path) {
+                Some(EntryVariant::Binary(_, arch))
+                | Some(EntryVariant::Contents(arch, _))
+                | Some(EntryVariant::ComponentContents(_, arch, _))
+                | Some(EntryVariant::Commands(_, arch, _))
+                | Some(EntryVariant::DebianInstaller(DebianInstallerEntry::Binary(_, arch)))
+                | Some(EntryVariant::DebianInstaller(DebianInstallerEntry::Contents(_, arch, _))) =>
+                {
+                    architectures.insert(<&'static str>::from(arch));
+                }
+                _ => (),
+            }
+        }
+
+        for algorithm in &self.algorithms {
+            release
+                .sums
+                .insert(algorithm.clone(), EntryComponents::default());
+        }
+
+        for path in paths {
+            let (size, sums) = hash_file(&self.dir.join(&path), &self.algorithms)?;
+
+            for (algorithm, sum) in self.algorithms.iter().zip(sums) {
+                if let Some(components) = release.sums.get_mut(algorithm) {
+                    components.insert(ReleaseEntry {
+                        sum,
+                        size,
+                        path: path.clone(),
+                    });
+                }
+            }
+        }
+
+        release.sums.retain(|_, components| !components.is_empty());
+
+        release.architectures = Some(architectures.into_iter().map(String::from).collect());
+        release.components = Some(components.into_iter().collect());
+
+        if release.date.is_none() {
+            // Release files only record the date to the second.
+            release.date = Some(Utc::now().trunc_subsecs(0));
+        }
+
+        Ok(release)
+    }
+}
+
+// Recursively collects the paths of index files, relative to the dists directory.
+fn collect_indices(dir: &Path, prefix: String, paths: &mut Vec<String>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+
+        let path = [&prefix, name.as_str()].concat();
+
+        if entry.file_type()?.is_dir() {
+            // Files in by-hash directories are copies of the indices that are already listed.
+            if name != "by-hash" {
+                collect_indices(&entry.path(), path + "/", paths)?;
+            }
+        } else if entry_variant(&path).is_some() {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+// Reads the file once, computing the digest of each algorithm.
+fn hash_file(path: &Path, algorithms: &[HashAlgorithm]) -> io::Result<(u64, Vec<String>)> {
+    let mut hashers = algorithms
+        .iter()
+        .filter_map(Hasher::new)
+        .collect::<Vec<Hasher>>();
+
+    let mut file = File::open(path)?;
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0;
+
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => {
+                for hasher in &mut hashers {
+                    hasher.update(&buffer[..read]);
+                }
+                size += read as u64;
+            }
+            Err(ref why) if why.kind() == io::ErrorKind::Interrupted => (),
+            Err(why) => return Err(why),
+        }
+    }
+
+    Ok((size, hashers.into_iter().map(Hasher::finish).collect()))
+}
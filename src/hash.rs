@@ -0,0 +1,310 @@
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest as _, Sha256, Sha512};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+
+/// The checksum method of a section in the release file, such as `SHA256`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    /// A checksum method that this crate does not recognize.
+    Unknown(String),
+}
+
+impl HashAlgorithm {
+    /// The name of the checksum section in a release file.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            HashAlgorithm::Md5 => "MD5Sum",
+            HashAlgorithm::Sha1 => "SHA1",
+            HashAlgorithm::Sha256 => "SHA256",
+            HashAlgorithm::Sha512 => "SHA512",
+            HashAlgorithm::Unknown(ref name) => name,
+        }
+    }
+
+    /// The length of the digest, in bytes, if the algorithm is known.
+    pub fn digest_len(&self) -> Option<usize> {
+        match *self {
+            HashAlgorithm::Md5 => Some(16),
+            HashAlgorithm::Sha1 => Some(20),
+            HashAlgorithm::Sha256 => Some(32),
+            HashAlgorithm::Sha512 => Some(64),
+            HashAlgorithm::Unknown(_) => None,
+        }
+    }
+
+    /// Checks that a hex-encoded sum has the length and alphabet of this algorithm.
+    ///
+    /// Sums for unknown algorithms are always considered valid.
+    pub fn validate(&self, sum: &str) -> Result<(), ChecksumError> {
+        let expected = match self.digest_len() {
+            Some(len) => len * 2,
+            None => return Ok(()),
+        };
+
+        if let Some(character) = sum.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(ChecksumError::InvalidCharacter(character));
+        }
+
+        if sum.len() != expected {
+            return Err(ChecksumError::InvalidLength {
+                expected,
+                found: sum.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether this crate is able to compute digests for this algorithm.
+    pub fn is_known(&self) -> bool {
+        self.digest_len().is_some()
+    }
+
+    /// Computes the hex-encoded digest of everything read from `reader`.
+    ///
+    /// Returns `None` if the algorithm is unknown.
+    pub fn digest<R: Read>(&self, reader: R) -> io::Result<Option<String>> {
+        match Hasher::new(self) {
+            Some(mut hasher) => hasher.consume(reader).map(|_| Some(hasher.finish())),
+            None => Ok(None),
+        }
+    }
+
+    /// Validates and decodes a hex-encoded sum into its digest bytes.
+    pub fn decode(&self, sum: &str) -> Result<Vec<u8>, ChecksumError> {
+        self.validate(sum)?;
+        decode_hex(sum)
+    }
+}
+
+/// A sum which is stored as the bytes of its digest, rather than as hex-encoded text.
+///
+/// Use `ReleaseEntry::decode` to obtain a `ReleaseEntry<Digest>`, whose known sums are held inline
+/// and compare equal regardless of the case of their hex digits.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum Digest {
+    Md5([u8; 16]),
+    Sha1([u8; 20]),
+    Sha256([u8; 32]),
+    Sha512([u8; 64]),
+    /// The sum of an algorithm that this crate does not recognize, as it is written.
+    Unknown(String),
+}
+
+impl Digest {
+    /// Validates and decodes a hex-encoded sum of the given algorithm.
+    pub fn decode(algorithm: &HashAlgorithm, sum: &str) -> Result<Self, ChecksumError> {
+        fn array<const N: usize>(
+            algorithm: &HashAlgorithm,
+            sum: &str,
+        ) -> Result<[u8; N], ChecksumError> {
+            let mut digest = [0; N];
+            digest.copy_from_slice(&algorithm.decode(sum)?);
+            Ok(digest)
+        }
+
+        Ok(match *algorithm {
+            HashAlgorithm::Md5 => Digest::Md5(array(algorithm, sum)?),
+            HashAlgorithm::Sha1 => Digest::Sha1(array(algorithm, sum)?),
+            HashAlgorithm::Sha256 => Digest::Sha256(array(algorithm, sum)?),
+            HashAlgorithm::Sha512 => Digest::Sha512(array(algorithm, sum)?),
+            HashAlgorithm::Unknown(_) => Digest::Unknown(sum.to_owned()),
+        })
+    }
+
+    /// The bytes of the digest, if its algorithm is known.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            Digest::Md5(ref digest) => Some(digest),
+            Digest::Sha1(ref digest) => Some(digest),
+            Digest::Sha256(ref digest) => Some(digest),
+            Digest::Sha512(ref digest) => Some(digest),
+            Digest::Unknown(_) => None,
+        }
+    }
+}
+
+/// Writes the digest as a lowercase hex-encoded sum, or an unknown sum as it was written.
+impl fmt::Display for Digest {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Digest::Unknown(ref sum) => fmt.write_str(sum),
+            _ => fmt.write_str(&encode_hex(self.as_bytes().unwrap_or_default())),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for HashAlgorithm {
+    fn from(name: &'a str) -> Self {
+        match name {
+            "MD5Sum" => HashAlgorithm::Md5,
+            "SHA1" => HashAlgorithm::Sha1,
+            "SHA256" => HashAlgorithm::Sha256,
+            "SHA512" => HashAlgorithm::Sha512,
+            other => HashAlgorithm::Unknown(other.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+/// Reasons that a checksum may be invalid for its algorithm.
+#[derive(Debug, Clone, Copy, Hash, PartialEq)]
+pub enum ChecksumError {
+    InvalidCharacter(char),
+    InvalidLength { expected: usize, found: usize },
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChecksumError::InvalidCharacter(character) => {
+                write!(fmt, "'{}' is not a hexadecimal digit", character)
+            }
+            ChecksumError::InvalidLength { expected, found } => write!(
+                fmt,
+                "expected {} hexadecimal digits, but found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl Error for ChecksumError {}
+
+// Incrementally computes a digest with one of the known algorithms.
+pub(crate) enum Hasher {
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    pub(crate) fn new(algorithm: &HashAlgorithm) -> Option<Self> {
+        match *algorithm {
+            HashAlgorithm::Md5 => Some(Hasher::Md5(Md5::new())),
+            HashAlgorithm::Sha1 => Some(Hasher::Sha1(Sha1::new())),
+            HashAlgorithm::Sha256 => Some(Hasher::Sha256(Sha256::new())),
+            HashAlgorithm::Sha512 => Some(Hasher::Sha512(Sha512::new())),
+            HashAlgorithm::Unknown(_) => None,
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match *self {
+            Hasher::Md5(ref mut hasher) => hasher.update(data),
+            Hasher::Sha1(ref mut hasher) => hasher.update(data),
+            Hasher::Sha256(ref mut hasher) => hasher.update(data),
+            Hasher::Sha512(ref mut hasher) => hasher.update(data),
+        }
+    }
+
+    pub(crate) fn consume<R: Read>(&mut self, mut reader: R) -> io::Result<u64> {
+        let mut buffer = [0u8; 64 * 1024];
+        let mut total = 0;
+
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(total),
+                Ok(read) => {
+                    self.update(&buffer[..read]);
+                    total += read as u64;
+                }
+                Err(ref why) if why.kind() == io::ErrorKind::Interrupted => (),
+                Err(why) => return Err(why),
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        match self {
+            Hasher::Md5(hasher) => encode_hex(&hasher.finalize()),
+            Hasher::Sha1(hasher) => encode_hex(&hasher.finalize()),
+            Hasher::Sha256(hasher) => encode_hex(&hasher.finalize()),
+            Hasher::Sha512(hasher) => encode_hex(&hasher.finalize()),
+        }
+    }
+}
+
+pub(crate) fn encode_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn decode_hex(sum: &str) -> Result<Vec<u8>, ChecksumError> {
+    if !sum.len().is_multiple_of(2) {
+        return Err(ChecksumError::InvalidLength {
+            expected: sum.len() + 1,
+            found: sum.len(),
+        });
+    }
+
+    sum.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let digit = |byte: u8| {
+                (byte as char)
+                    .to_digit(16)
+                    .map(|digit| digit as u8)
+                    .ok_or(ChecksumError::InvalidCharacter(byte as char))
+            };
+
+            Ok(digit(pair[0])? << 4 | digit(pair[1])?)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sum_validation() {
+        let md5 = HashAlgorithm::Md5;
+        assert!(md5.validate("3439b462cea992ac689c9047ce7a5463").is_ok());
+        assert_eq!(
+            md5.validate("3439b462cea992ac689c9047ce7a546"),
+            Err(ChecksumError::InvalidLength {
+                expected: 32,
+                found: 31
+            })
+        );
+        assert_eq!(
+            md5.validate("3439b462cea992ac689c9047ce7a546z"),
+            Err(ChecksumError::InvalidCharacter('z'))
+        );
+        assert_eq!(
+            md5.decode("000102030405060708090a0b0c0d0eff").unwrap(),
+            vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 255]
+        );
+    }
+
+    #[test]
+    fn digests() {
+        let digest = |algorithm: HashAlgorithm| algorithm.digest(&b"abc"[..]).unwrap();
+        assert_eq!(
+            digest(HashAlgorithm::Md5),
+            Some("900150983cd24fb0d6963f7d28e17f72".into())
+        );
+        assert_eq!(
+            digest(HashAlgorithm::Sha1),
+            Some("a9993e364706816aba3e25717850c26c9cd0d89d".into())
+        );
+        assert_eq!(
+            digest(HashAlgorithm::Sha256),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".into())
+        );
+        assert_eq!(digest(HashAlgorithm::Unknown("CRC32".into())), None);
+    }
+}
@@ -1,19 +1,24 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
 use std::str::FromStr;
 
 /// The size of an icon package. IE: `48x48@2`
 #[derive(Debug, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ImageSize {
     pub pixels: u16,
     pub hidpi: u16,
 }
 
 impl FromStr for ImageSize {
-    type Err = &'static str;
+    type Err = ImageSizeError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         input
             .find('x')
-            .ok_or("not a recognizable image string")
+            .ok_or(ImageSizeError::Unrecognized)
             .and_then(|pos| {
                 let x = &input[..pos];
                 let y = &input[pos + 1..];
@@ -23,23 +28,50 @@ impl FromStr for ImageSize {
                         let y = &y[..pos];
 
                         match (x.parse::<u16>(), y.parse::<u16>(), z.parse::<u16>()) {
-                            (Ok(pixels), Ok(y), Ok(hidpi)) => if pixels == y {
-                                Ok(ImageSize { pixels, hidpi })
-                            } else {
-                                Err("width and height do not match")
-                            },
-                            _ => Err("width, height, and/or scale did not parse as integers"),
+                            (Ok(pixels), Ok(y), Ok(hidpi)) => {
+                                if pixels == y {
+                                    Ok(ImageSize { pixels, hidpi })
+                                } else {
+                                    Err(ImageSizeError::Mismatched)
+                                }
+                            }
+                            _ => Err(ImageSizeError::NotAnInteger),
                         }
                     }
                     None => match (x.parse::<u16>(), y.parse::<u16>()) {
-                        (Ok(pixels), Ok(y)) => if pixels == y {
-                            Ok(ImageSize { pixels, hidpi: 0 })
-                        } else {
-                            Err("width and height do not match")
-                        },
-                        _ => Err("width and/or height failed to parse as integers"),
+                        (Ok(pixels), Ok(y)) => {
+                            if pixels == y {
+                                Ok(ImageSize { pixels, hidpi: 0 })
+                            } else {
+                                Err(ImageSizeError::Mismatched)
+                            }
+                        }
+                        _ => Err(ImageSizeError::NotAnInteger),
                     },
                 }
             })
     }
 }
+
+/// Reasons that an image size may fail to parse.
+#[derive(Debug, Clone, Copy, Hash, PartialEq)]
+pub enum ImageSizeError {
+    /// The input is not in the form of `WxH` or `WxH@S`.
+    Unrecognized,
+    /// The width and height are not the same.
+    Mismatched,
+    /// The width, height, or scale did not parse as an integer.
+    NotAnInteger,
+}
+
+impl fmt::Display for ImageSizeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match *self {
+            ImageSizeError::Unrecognized => "not a recognizable image string",
+            ImageSizeError::Mismatched => "width and height do not match",
+            ImageSizeError::NotAnInteger => "width, height, and/or scale did not parse as integers",
+        })
+    }
+}
+
+impl Error for ImageSizeError {}
@@ -0,0 +1,176 @@
+use super::{DistRelease, ParseError};
+use std::error::Error;
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, fs, io};
+
+const BEGIN_MESSAGE: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----";
+const END_SIGNATURE: &str = "-----END PGP SIGNATURE-----";
+
+/// A clearsigned `InRelease` file, which embeds the dist release file within an OpenPGP
+/// cleartext signature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InRelease {
+    /// The digest algorithms listed in the `Hash:` armor headers.
+    pub hashes: Vec<String>,
+    /// The release file that was parsed from the signed text.
+    pub release: DistRelease,
+    /// The signed text, with dash-escaping undone, exactly as it was signed.
+    pub signed_text: String,
+    /// The ASCII-armored signature block, which may be verified against the signed text.
+    pub signature: Vec<u8>,
+}
+
+impl InRelease {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        fs::read_to_string(path).and_then(|string| string.parse::<Self>().map_err(io::Error::from))
+    }
+}
+
+impl FromStr for InRelease {
+    type Err = InReleaseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (skipped, hashes, signed_text, signature) = split_clearsigned(input)?;
+
+        // Lines of the release file are numbered from the start of the InRelease file.
+        let release = signed_text
+            .parse::<DistRelease>()
+            .map_err(|why| InReleaseError::Parse(why.offset(skipped)))?;
+
+        Ok(InRelease {
+            hashes,
+            release,
+            signed_text,
+            signature,
+        })
+    }
+}
+
+/// An error that occurred while parsing a clearsigned release file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InReleaseError {
+    /// The file does not follow the cleartext signature framework, such as by missing its
+    /// signature block.
+    InvalidArmor(String),
+    /// The signed release file is invalid. Its lines are numbered from the start of the
+    /// InRelease file.
+    Parse(ParseError),
+}
+
+impl fmt::Display for InReleaseError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InReleaseError::InvalidArmor(ref why) => {
+                write!(fmt, "invalid clearsigned release file: {}", why)
+            }
+            InReleaseError::Parse(ref why) => fmt::Display::fmt(why, fmt),
+        }
+    }
+}
+
+impl Error for InReleaseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            InReleaseError::Parse(ref why) => Some(why),
+            InReleaseError::InvalidArmor(_) => None,
+        }
+    }
+}
+
+impl From<InReleaseError> for io::Error {
+    fn from(why: InReleaseError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, why)
+    }
+}
+
+// Separates the armor headers, the dash-unescaped signed text, and the signature block, along
+// with the number of lines which precede the signed text.
+fn split_clearsigned(input: &str) -> Result<(usize, Vec<String>, String, Vec<u8>), InReleaseError> {
+    let mut iterator = input.lines().enumerate();
+
+    match iterator.find(|&(_, line)| !line.trim().is_empty()) {
+        Some((_, line)) if line.trim_end() == BEGIN_MESSAGE => (),
+        _ => return Err(invalid_armor("missing signed message header")),
+    }
+
+    let mut hashes = Vec::new();
+
+    let skipped = loop {
+        match iterator.next() {
+            Some((index, line)) if line.trim().is_empty() => break index + 1,
+            Some((_, line)) => {
+                if let Some(value) = line.strip_prefix("Hash:") {
+                    hashes.extend(value.split(',').map(|hash| hash.trim().to_owned()));
+                }
+            }
+            None => return Err(invalid_armor("armor headers are not terminated")),
+        }
+    };
+
+    let mut iterator = input.lines().skip(skipped);
+    let mut signed = Vec::new();
+
+    loop {
+        match iterator.next() {
+            Some(line) if line.trim_end() == BEGIN_SIGNATURE => break,
+            // Lines beginning with a dash are escaped with a leading `- `.
+            Some(line) => signed.push(line.strip_prefix("- ").unwrap_or(line)),
+            None => return Err(invalid_armor("missing signature block")),
+        }
+    }
+
+    let mut signature = String::from(BEGIN_SIGNATURE);
+    signature.push('\n');
+
+    loop {
+        match iterator.next() {
+            Some(line) => {
+                signature.push_str(line);
+                signature.push('\n');
+                if line.trim_end() == END_SIGNATURE {
+                    break;
+                }
+            }
+            None => return Err(invalid_armor("signature block is not terminated")),
+        }
+    }
+
+    Ok((skipped, hashes, signed.join("\n"), signature.into_bytes()))
+}
+
+fn invalid_armor(message: &str) -> InReleaseError {
+    InReleaseError::InvalidArmor(message.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dash_escaping() {
+        let input = "-----BEGIN PGP SIGNED MESSAGE-----\n\
+                     Hash: SHA256\n\
+                     \n\
+                     Codename: cosmic\n\
+                     Description: - dashed\n\
+                     - -dashed\n\
+                     -----BEGIN PGP SIGNATURE-----\n\
+                     \n\
+                     abcd\n\
+                     -----END PGP SIGNATURE-----\n";
+
+        let (skipped, hashes, signed_text, signature) = split_clearsigned(input).unwrap();
+        assert_eq!(skipped, 3);
+        assert_eq!(hashes, vec!["SHA256".to_owned()]);
+        assert_eq!(
+            signed_text,
+            "Codename: cosmic\nDescription: - dashed\n-dashed"
+        );
+        assert_eq!(
+            signature,
+            b"-----BEGIN PGP SIGNATURE-----\n\nabcd\n-----END PGP SIGNATURE-----\n".to_vec()
+        );
+    }
+}
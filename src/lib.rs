@@ -1,212 +1,342 @@
+extern crate bzip2;
 extern crate chrono;
 extern crate deb_architectures;
-#[macro_use]
-extern crate smart_default;
+extern crate flate2;
+extern crate lz4_flex;
+extern crate md5;
+#[cfg(feature = "openpgp")]
+extern crate pgp;
+#[cfg(feature = "serde")]
+extern crate serde;
+extern crate sha1;
+extern crate sha2;
+extern crate xz2;
+extern crate zstd;
 
+mod borrowed;
+mod compression;
+mod contents;
+mod control;
+mod diff;
 mod entry;
+mod error;
+mod files;
+mod generate;
+mod hash;
 mod image_size;
+mod inrelease;
+mod packages;
+mod patch;
+mod pdiff;
+mod relation;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "openpgp")]
+mod signature;
+mod sources;
+mod stream;
 mod time;
+mod validity;
+mod verify;
 
+pub use self::borrowed::*;
+pub use self::compression::*;
+pub use self::contents::*;
+pub use self::diff::*;
 pub use self::entry::*;
+pub use self::error::*;
+pub use self::files::*;
+pub use self::generate::*;
+pub use self::hash::*;
 pub use self::image_size::*;
+pub use self::inrelease::*;
+pub use self::packages::*;
+pub use self::patch::*;
+pub use self::pdiff::*;
+pub use self::relation::*;
+#[cfg(feature = "openpgp")]
+pub use self::signature::*;
+pub use self::sources::*;
+pub use self::stream::*;
+pub use self::validity::*;
+pub use self::verify::*;
 
-use self::time::get_time;
+use self::borrowed::is_known_field;
+use self::time::DATE_FORMAT;
 use chrono::{DateTime, Utc};
-use std::collections::BTreeMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
 use std::path::Path;
 use std::str::FromStr;
-use std::{fs, io};
+use std::{fmt, fs, io};
 
 /// The dist release file is a file in the apt repository that points to all other dist files in the archive.
-#[derive(Debug, SmartDefault, Clone, PartialEq)]
+///
+/// Every field in the header of a release file is optional, so fields which were not defined
+/// are `None`.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct DistRelease {
-    pub architectures: Vec<String>,
-    pub codename: String,
-    pub components: Vec<String>,
-    #[default(Utc::now())]
-    pub date: DateTime<Utc>,
-    pub description: String,
-    pub label: String,
-    pub origin: String,
-    pub suite: String,
-    pub version: String,
-    pub sums: BTreeMap<String, EntryComponents>,
+    pub acquire_by_hash: Option<bool>,
+    pub architectures: Option<Vec<String>>,
+    pub but_automatic_upgrades: Option<bool>,
+    pub changelogs: Option<String>,
+    pub codename: Option<String>,
+    pub components: Option<Vec<String>>,
+    pub date: Option<DateTime<Utc>>,
+    pub description: Option<String>,
+    pub label: Option<String>,
+    pub no_support_for_architecture_all: Option<String>,
+    pub not_automatic: Option<bool>,
+    pub origin: Option<String>,
+    pub signed_by: Option<Vec<String>>,
+    pub snapshots: Option<String>,
+    pub suite: Option<String>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub version: Option<String>,
+    /// Every field of the header, in the order that it was defined, as it is written.
+    ///
+    /// Fields are written back in this order and with this text, unless their typed value has
+    /// since been changed. Fields which are not otherwise recognized are only stored here.
+    pub fields: Vec<(String, String)>,
+    /// The checksum sections, in the order that they were defined.
+    ///
+    /// Sections are written back in this order. Sections which were only added to `sums` follow,
+    /// ordered from the weakest to the strongest algorithm.
+    pub sections: Vec<HashAlgorithm>,
+    pub sums: BTreeMap<HashAlgorithm, EntryComponents>,
 }
 
 impl DistRelease {
     pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        fs::read_to_string(path).and_then(|string| string.parse::<Self>())
+        fs::read_to_string(path).and_then(|string| string.parse::<Self>().map_err(io::Error::from))
     }
-}
 
-impl FromStr for DistRelease {
-    type Err = io::Error;
+    /// Fields which are not otherwise recognized, in the order that they were defined.
+    pub fn extra_fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields
+            .iter()
+            .filter(|&(key, _)| !is_known_field(key))
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
 
-    fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let mut iterator = input.lines();
-
-        let mut release = DistRelease::default();
-
-        #[derive(Copy, Clone)]
-        enum Variant {
-            Archs,
-            Codename,
-            Components,
-            Date,
-            Description,
-            Label,
-            Origin,
-            Suite,
-            Version,
+    // The header fields which are defined, in the order and form that they are written.
+    //
+    // Fields keep the position and text that they were parsed with. Fields which were only
+    // defined in code follow, in alphabetical order.
+    pub(crate) fn header_fields(&self) -> Vec<(&str, String)> {
+        let mut typed = self.typed_fields();
+        let mut fields = Vec::with_capacity(self.fields.len() + typed.len());
+
+        for (key, text) in &self.fields {
+            if !is_known_field(key) {
+                fields.push((key.as_str(), text.clone()));
+                continue;
+            }
+
+            // Fields which have since been removed are skipped.
+            if let Some(pos) = typed.iter().position(|&(other, _)| other == key) {
+                let (key, value) = typed.remove(pos);
+                let value = if is_unchanged(key, text, &value) {
+                    text.clone()
+                } else {
+                    value
+                };
+
+                fields.push((key, value));
+            }
         }
 
-        let mut entries = vec![
-            ("Architectures:", Variant::Archs),
-            ("Codename:", Variant::Codename),
-            ("Components:", Variant::Components),
-            ("Date:", Variant::Date),
-            ("Description:", Variant::Description),
-            ("Label:", Variant::Label),
-            ("Origin:", Variant::Origin),
-            ("Suite:", Variant::Suite),
-            ("Version:", Variant::Version),
-        ];
+        fields.extend(typed);
+        fields
+    }
+
+    // The typed fields which are defined, in alphabetical order and in the form that they are
+    // written.
+    fn typed_fields(&self) -> Vec<(&'static str, String)> {
+        fn string(value: &Option<String>) -> Option<String> {
+            value.clone()
+        }
 
-        let mut remove = None;
+        fn vec(value: &Option<Vec<String>>) -> Option<String> {
+            value.as_ref().map(|value| value.join(" "))
+        }
 
-        fn get_string(value: &str) -> String {
-            value.trim().to_owned()
+        fn date(value: &Option<DateTime<Utc>>) -> Option<String> {
+            value.map(|value| value.format(DATE_FORMAT).to_string())
         }
 
-        fn get_vec(value: &str) -> Vec<String> {
-            value.split_whitespace().map(String::from).collect()
+        fn boolean(value: Option<bool>) -> Option<String> {
+            value.map(|value| if value { "yes" } else { "no" }.to_owned())
         }
 
-        let mut sum = None;
+        let fields = vec![
+            ("Acquire-By-Hash", boolean(self.acquire_by_hash)),
+            ("Architectures", vec(&self.architectures)),
+            ("ButAutomaticUpgrades", boolean(self.but_automatic_upgrades)),
+            ("Changelogs", string(&self.changelogs)),
+            ("Codename", string(&self.codename)),
+            ("Components", vec(&self.components)),
+            ("Date", date(&self.date)),
+            ("Description", string(&self.description)),
+            ("Label", string(&self.label)),
+            (
+                "No-Support-for-Architecture-all",
+                string(&self.no_support_for_architecture_all),
+            ),
+            ("NotAutomatic", boolean(self.not_automatic)),
+            ("Origin", string(&self.origin)),
+            ("Signed-By", vec(&self.signed_by)),
+            ("Snapshots", string(&self.snapshots)),
+            ("Suite", string(&self.suite)),
+            ("Valid-Until", date(&self.valid_until)),
+            ("Version", string(&self.version)),
+        ];
 
-        while !entries.is_empty() {
-            let line = iterator.next().unwrap();
+        fields
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .collect()
+    }
+}
 
-            for (id, &(ref key, variant)) in entries.iter().enumerate() {
-                if line.starts_with(key) {
-                    remove = Some(id);
+// Whether the text that a field was parsed from still has the same value, so that the spelling of
+// the original, such as a date in `+0000` rather than `UTC`, is kept.
+fn is_unchanged(key: &str, text: &str, value: &str) -> bool {
+    DistReleaseRef::parse(&[key, ": ", text].concat()).is_ok_and(|release| {
+        release
+            .into_owned()
+            .typed_fields()
+            .iter()
+            .any(|(_, other)| other == value)
+    })
+}
 
-                    let value = &line[key.len()..];
+impl FromStr for DistRelease {
+    type Err = ParseError;
 
-                    match variant {
-                        Variant::Archs => release.architectures = get_vec(value),
-                        Variant::Codename => release.codename = get_string(value),
-                        Variant::Components => release.components = get_vec(value),
-                        Variant::Date => release.date = get_time(value)?,
-                        Variant::Description => release.description = get_string(value),
-                        Variant::Label => release.label = get_string(value),
-                        Variant::Origin => release.origin = get_string(value),
-                        Variant::Suite => release.suite = get_string(value),
-                        Variant::Version => release.version = get_string(value),
-                    }
-                }
-            }
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        DistReleaseRef::parse(input).map(DistReleaseRef::into_owned)
+    }
+}
 
-            if let Some(pos) = remove.take() {
-                entries.remove(pos);
-            } else if line.ends_with(':') {
-                sum = Some(line.trim()[..line.len() - 1].to_owned());
-                break
-            } else {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("unknown key in release file: {}", line),
-                ));
-            }
+impl fmt::Display for DistRelease {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (key, value) in self.header_fields() {
+            writeln!(fmt, "{}: {}", key, value)?;
         }
 
-        let mut active_hash = sum.unwrap_or_default();
-        let mut active_components = EntryComponents::default();
-
-        for line in iterator {
-            if line.starts_with(' ') {
-                match line.parse::<ReleaseEntry>() {
-                    Ok(mut entry) => {
-                        let mut path = String::new();
-                        ::std::mem::swap(&mut path, &mut entry.path);
-                        let base = match path.find('.') {
-                            Some(pos) => &path[..pos],
-                            None => &path,
-                        };
-
-                        match path.find('/') {
-                            Some(pos) => {
-                                let component = &path[..pos];
-                                // TODO: Prevent this allocation.
-                                entry.path = path[pos + 1..].to_owned();
-
-                                active_components
-                                    .components
-                                    .entry(component.into())
-                                    .and_modify(|e| {
-                                        e.entry(base.into())
-                                            .and_modify(|e| e.push(entry.to_owned()))
-                                            .or_insert_with(|| vec![entry.to_owned()]);
-                                    })
-                                    .or_insert_with(|| {
-                                        let mut map = BTreeMap::new();
-                                        map.insert(base.into(), vec![entry.to_owned()]);
-                                        map
-                                    });
-                            }
-                            None => {
-                                entry.path = path.clone();
-                                active_components
-                                    .base
-                                    .entry(base.into())
-                                    .and_modify(|e| e.push(entry.to_owned()))
-                                    .or_insert_with(|| vec![entry.to_owned()]);
-                            }
-                        }
-                    }
-                    Err(why) => {
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            format!("invalid checksum entry: {}", why),
-                        ))
-                    }
-                }
-            } else {
-                if !active_components.is_empty() {
-                    release
-                        .sums
-                        .insert(active_hash.clone(), active_components.clone());
-                    active_components.clear();
-                }
+        let sections = self.sections.iter().chain(
+            self.sums
+                .keys()
+                .filter(|hash| !self.sections.contains(hash)),
+        );
 
-                active_hash.clear();
-                active_hash.push_str(line.trim());
-                active_hash.pop();
+        for hash in sections {
+            // Sections which have since been removed are skipped.
+            if let Some(components) = self.sums.get(hash) {
+                writeln!(fmt, "{}:", hash)?;
+                components.write_entries(fmt)?;
             }
         }
 
-        if !active_components.is_empty() {
-            release.sums.insert(active_hash, active_components);
-        }
-
-        Ok(release)
+        Ok(())
     }
 }
 
 /// Stores the entries for each component for this checksum method.
 #[derive(Debug, Default, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct EntryComponents {
     pub base: BTreeMap<String, Vec<ReleaseEntry>>,
     pub components: BTreeMap<String, BTreeMap<String, Vec<ReleaseEntry>>>,
+    /// The paths of the entries, relative to the `dists/<suite>/` directory, in the order that
+    /// they were inserted, which is the order that they are written in.
+    pub order: Vec<String>,
 }
 
 impl EntryComponents {
     pub fn clear(&mut self) {
         self.base.clear();
         self.components.clear();
+        self.order.clear();
     }
 
     pub fn is_empty(&self) -> bool {
         self.base.is_empty() && self.components.is_empty()
     }
+
+    /// Adds an entry to the base, or to the component that its path begins with.
+    pub fn insert(&mut self, mut entry: ReleaseEntry) {
+        self.order.push(entry.path.clone());
+
+        let base = match entry.path.find('.') {
+            Some(pos) => entry.path[..pos].to_owned(),
+            None => entry.path.clone(),
+        };
+
+        match entry.path.find('/') {
+            Some(pos) => {
+                let component = entry.path[..pos].to_owned();
+                entry.path.drain(..pos + 1);
+
+                self.components
+                    .entry(component)
+                    .or_default()
+                    .entry(base)
+                    .or_default()
+                    .push(entry);
+            }
+            None => self.base.entry(base).or_default().push(entry),
+        }
+    }
+
+    /// Iterates every entry, along with the component that the entry belongs to.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (Option<&'a str>, &'a ReleaseEntry)> + 'a {
+        let base = self
+            .base
+            .values()
+            .flat_map(|entries| entries.iter())
+            .map(|entry| (None, entry));
+
+        let components = self.components.iter().flat_map(|(component, entries)| {
+            entries
+                .values()
+                .flat_map(|entries| entries.iter())
+                .map(move |entry| (Some(component.as_str()), entry))
+        });
+
+        base.chain(components)
+    }
+
+    // Entries are written in the order that they were inserted, in the same column layout that
+    // apt-ftparchive generates. Entries which were only added to the maps follow.
+    fn write_entries(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let mut written = HashSet::new();
+
+        for path in &self.order {
+            if let Some(entry) = self.get(path) {
+                if written.insert(path.as_str()) {
+                    writeln!(fmt, " {} {:>16} {}", entry.sum, entry.size, path)?;
+                }
+            }
+        }
+
+        for (component, entry) in self.iter() {
+            let path = match component {
+                Some(component) => Cow::Owned([component, "/", &entry.path].concat()),
+                None => Cow::Borrowed(entry.path.as_str()),
+            };
+
+            if !written.contains(&*path) {
+                writeln!(fmt, " {} {:>16} {}", entry.sum, entry.size, path)?;
+            }
+        }
+
+        Ok(())
+    }
 }
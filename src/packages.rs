@@ -0,0 +1,117 @@
+use super::compression::read_index;
+use super::control::{paragraphs, Paragraph};
+use super::{parse_relations, HashAlgorithm, ParseError, Relations};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A binary package index, which a `binary-<arch>/Packages` entry of the release file points to.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Packages {
+    pub packages: Vec<Package>,
+}
+
+impl Packages {
+    /// Reads an index from a file, which is decompressed according to its extension.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        read_index(path).and_then(|string| string.parse::<Self>().map_err(io::Error::from))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Package> {
+        self.packages.iter()
+    }
+}
+
+impl FromStr for Packages {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let packages = paragraphs(input)?
+            .iter()
+            .map(Package::from_paragraph)
+            .collect::<Result<Vec<Package>, ParseError>>()?;
+
+        Ok(Packages { packages })
+    }
+}
+
+/// A stanza of a Packages index, describing a single binary package.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Package {
+    pub package: String,
+    pub version: String,
+    pub architecture: String,
+    pub depends: Relations,
+    pub pre_depends: Relations,
+    pub recommends: Relations,
+    /// The path of the `.deb` archive, relative to the root of the repository.
+    pub filename: String,
+    pub size: u64,
+    pub md5sum: Option<String>,
+    pub sha256: Option<String>,
+    /// The synopsis, followed by the extended description on the lines that follow it.
+    pub description: Option<String>,
+    /// Fields which are not otherwise recognized, in the order that they were defined.
+    ///
+    /// Values which span multiple lines are joined by newlines.
+    pub extra_fields: Vec<(String, String)>,
+}
+
+impl Package {
+    fn from_paragraph(paragraph: &Paragraph) -> Result<Self, ParseError> {
+        let mut package = Package::default();
+
+        for field in &paragraph.fields {
+            let invalid_value = |reason: String| ParseError::InvalidValue {
+                position: field.position(),
+                reason: format!("{}: {}", field.key, reason),
+            };
+
+            let relations =
+                || parse_relations(&field.value).map_err(|why| invalid_value(why.to_string()));
+
+            let checksum = |algorithm: HashAlgorithm| match algorithm.validate(&field.value) {
+                Ok(()) => Ok(Some(field.value.clone())),
+                Err(reason) => Err(ParseError::InvalidChecksum {
+                    position: field.position(),
+                    reason,
+                }),
+            };
+
+            match field.key {
+                "Package" => package.package = field.value.clone(),
+                "Version" => package.version = field.value.clone(),
+                "Architecture" => package.architecture = field.value.clone(),
+                "Depends" => package.depends = relations()?,
+                "Pre-Depends" => package.pre_depends = relations()?,
+                "Recommends" => package.recommends = relations()?,
+                "Filename" => package.filename = field.value.clone(),
+                "Size" => {
+                    package.size = field.value.parse().map_err(|_| ParseError::InvalidSize {
+                        position: field.position(),
+                    })?
+                }
+                "MD5sum" => package.md5sum = checksum(HashAlgorithm::Md5)?,
+                "SHA256" => package.sha256 = checksum(HashAlgorithm::Sha256)?,
+                "Description" => package.description = Some(field.value.clone()),
+                key => package
+                    .extra_fields
+                    .push((key.to_owned(), field.value.clone())),
+            }
+        }
+
+        for &key in &["Package", "Version", "Architecture", "Filename", "Size"] {
+            if paragraph.get(key).is_none() {
+                return Err(paragraph.missing(key));
+            }
+        }
+
+        Ok(package)
+    }
+}
@@ -0,0 +1,306 @@
+use super::hash::Hasher;
+use super::{
+    Compression, HashAlgorithm, IndexReader, Mismatch, PdiffIndex, ReleaseEntry, ReleaseFile,
+};
+use std::error::Error;
+use std::io::{self, Read};
+use std::path::Path;
+use std::{fmt, fs, str};
+
+impl PdiffIndex {
+    /// Whether each patch updates its version of the index directly to the current version,
+    /// rather than to the version which follows it.
+    pub fn is_merged(&self) -> bool {
+        self.extra_fields
+            .iter()
+            .any(|(key, value)| key == "X-Patch-Precedence" && value == "merged")
+    }
+
+    /// The patches which update the version of the index with the given SHA256 sum to the
+    /// current version, in the order that they must be applied.
+    ///
+    /// Returns `None` if the version is not in the history of the index.
+    pub fn patches_from(&self, sha256: &str) -> Option<Vec<&ReleaseEntry>> {
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|(sum, _)| sum.eq_ignore_ascii_case(sha256))
+        {
+            return Some(Vec::new());
+        }
+
+        let pos = self
+            .history
+            .iter()
+            .position(|entry| entry.sum.eq_ignore_ascii_case(sha256))?;
+
+        if self.is_merged() {
+            Some(vec![&self.history[pos]])
+        } else {
+            Some(self.history[pos..].iter().collect())
+        }
+    }
+
+    /// Reconstructs the current version of an index from a local copy and the gzip-compressed
+    /// patches that were downloaded into `patch_dir`, named as they are in the `.diff/Index`.
+    ///
+    /// Every patch is checked against the sums of the `.diff/Index` before it is applied, and
+    /// the result is checked against both the `.diff/Index` and the SHA256 sum of the index in
+    /// the new release file, such as the one returned by `DistRelease::get`.
+    pub fn apply<P, Q>(
+        &self,
+        local: P,
+        patch_dir: Q,
+        target: &ReleaseFile,
+    ) -> Result<Vec<u8>, PdiffError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let mut content = Vec::new();
+        IndexReader::open(local)?.read_to_end(&mut content)?;
+
+        let sum = sha256(&content);
+        let patches = self
+            .patches_from(&sum)
+            .ok_or(PdiffError::UnknownVersion { sum })?;
+
+        for history in patches {
+            let name = &history.path;
+
+            let download = [name, ".gz"].concat();
+            let compressed = fs::read(patch_dir.as_ref().join(&download))?;
+            check(&download, &self.download, &compressed)?;
+
+            let mut script = Vec::new();
+            Compression::Gzip
+                .decoder(&compressed[..])?
+                .read_to_end(&mut script)?;
+            check(name, &self.patches, &script)?;
+
+            content = apply_ed(name, &content, &script)?;
+        }
+
+        let (ref expected, size) = match self.current {
+            Some(ref current) => current.clone(),
+            None => {
+                return Err(PdiffError::MissingSum {
+                    name: target.path.clone(),
+                })
+            }
+        };
+
+        compare(&target.path, expected, size, &content)?;
+
+        match target.sums.get(&HashAlgorithm::Sha256) {
+            Some(expected) => compare(&target.path, expected, target.size, &content)?,
+            None => {
+                return Err(PdiffError::MissingSum {
+                    name: target.path.clone(),
+                })
+            }
+        }
+
+        Ok(content)
+    }
+}
+
+/// Reasons that pdiff patches could not be applied to an index.
+#[derive(Debug)]
+pub enum PdiffError {
+    Io(io::Error),
+    /// The local index is neither the current version nor any version in the history.
+    UnknownVersion {
+        sum: String,
+    },
+    /// A patch, or the index itself, has no SHA256 sum to be checked against.
+    MissingSum {
+        name: String,
+    },
+    /// A patch is not a valid ed script for the index.
+    InvalidPatch {
+        name: String,
+        line: usize,
+        reason: String,
+    },
+    /// A patch, or the reconstructed index, does not match its expected size or SHA256 sum.
+    Mismatch(Mismatch),
+}
+
+impl fmt::Display for PdiffError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PdiffError::Io(ref why) => write!(fmt, "failed to read patches: {}", why),
+            PdiffError::UnknownVersion { ref sum } => {
+                write!(fmt, "local index is not in the pdiff history: {}", sum)
+            }
+            PdiffError::MissingSum { ref name } => write!(fmt, "no SHA256 sum for {}", name),
+            PdiffError::InvalidPatch {
+                ref name,
+                line,
+                ref reason,
+            } => write!(fmt, "{}: line {}: {}", name, line, reason),
+            PdiffError::Mismatch(ref mismatch) => fmt::Display::fmt(mismatch, fmt),
+        }
+    }
+}
+
+impl Error for PdiffError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            PdiffError::Io(ref why) => Some(why),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PdiffError {
+    fn from(why: io::Error) -> Self {
+        PdiffError::Io(why)
+    }
+}
+
+fn sha256(data: &[u8]) -> String {
+    let mut hasher = Hasher::new(&HashAlgorithm::Sha256).expect("algorithm is known");
+    hasher.update(data);
+    hasher.finish()
+}
+
+// Checks a patch against its row in one of the SHA256 fields of the `.diff/Index`.
+fn check(name: &str, rows: &[ReleaseEntry], data: &[u8]) -> Result<(), PdiffError> {
+    match rows.iter().find(|row| row.path == name) {
+        Some(row) => compare(name, &row.sum, row.size, data),
+        None => Err(PdiffError::MissingSum {
+            name: name.to_owned(),
+        }),
+    }
+}
+
+fn compare(path: &str, expected: &str, size: u64, data: &[u8]) -> Result<(), PdiffError> {
+    if data.len() as u64 != size {
+        return Err(PdiffError::Mismatch(Mismatch::Size {
+            path: path.to_owned(),
+            expected: size,
+            found: data.len() as u64,
+        }));
+    }
+
+    let found = sha256(data);
+    if !found.eq_ignore_ascii_case(expected) {
+        return Err(PdiffError::Mismatch(Mismatch::Checksum {
+            path: path.to_owned(),
+            algorithm: HashAlgorithm::Sha256,
+            expected: expected.to_owned(),
+            found,
+        }));
+    }
+
+    Ok(())
+}
+
+// Applies an ed script, as produced by `diff --ed`, whose commands are ordered from the end of
+// the file to the beginning.
+fn apply_ed(name: &str, content: &[u8], script: &[u8]) -> Result<Vec<u8>, PdiffError> {
+    let mut lines = split_lines(content);
+    let mut commands = split_lines(script).into_iter().enumerate();
+
+    let invalid = |line: usize, reason: &str| PdiffError::InvalidPatch {
+        name: name.to_owned(),
+        line: line + 1,
+        reason: reason.to_owned(),
+    };
+
+    while let Some((number, command)) = commands.next() {
+        let command = str::from_utf8(command).map_err(|_| invalid(number, "not UTF-8"))?;
+
+        let (range, action) = match command.char_indices().last() {
+            Some((pos, action)) => (&command[..pos], action),
+            None => return Err(invalid(number, "empty command")),
+        };
+
+        let parse = |value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|_| invalid(number, "invalid line number"))
+        };
+
+        let (start, end) = match range.find(',') {
+            Some(pos) => (parse(&range[..pos])?, parse(&range[pos + 1..])?),
+            None => (parse(range)?, parse(range)?),
+        };
+
+        if end < start || end > lines.len() || (start == 0 && action != 'a') {
+            return Err(invalid(number, "line number is out of range"));
+        }
+
+        let mut text = Vec::new();
+        if action == 'a' || action == 'c' {
+            loop {
+                match commands.next() {
+                    Some((_, b".")) => break,
+                    Some((_, line)) => text.push(line),
+                    None => return Err(invalid(number, "text is not terminated by '.'")),
+                }
+            }
+        }
+
+        match action {
+            'a' => {
+                lines.splice(start..start, text);
+            }
+            'c' => {
+                lines.splice(start - 1..end, text);
+            }
+            'd' => {
+                lines.drain(start - 1..end);
+            }
+            _ => return Err(invalid(number, "unsupported command")),
+        }
+    }
+
+    let mut output = Vec::with_capacity(content.len());
+    for line in lines {
+        output.extend_from_slice(line);
+        output.push(b'\n');
+    }
+
+    Ok(output)
+}
+
+// Splits text into lines, without their line endings.
+fn split_lines(data: &[u8]) -> Vec<&[u8]> {
+    let data = data.strip_suffix(b"\n").unwrap_or(data);
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    data.split(|&byte| byte == b'\n').collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ed_scripts() {
+        let content = b"one\ntwo\nthree\nfour\n";
+        let script = b"4c\nFOUR\nfive\n.\n2,3d\n0a\nzero\n.\n";
+
+        assert_eq!(
+            apply_ed("patch", content, script).unwrap(),
+            b"zero\none\nFOUR\nfive\n".to_vec()
+        );
+
+        match apply_ed("patch", content, b"9d\n") {
+            Err(PdiffError::InvalidPatch { line, .. }) => assert_eq!(line, 1),
+            other => panic!("expected an invalid patch: {:?}", other),
+        }
+
+        match apply_ed("patch", content, b"1a\nunterminated\n") {
+            Err(PdiffError::InvalidPatch { reason, .. }) => {
+                assert_eq!(reason, "text is not terminated by '.'")
+            }
+            other => panic!("expected an invalid patch: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use super::compression::read_index;
+use super::control::{paragraphs, Field};
+use super::{HashAlgorithm, ParseError, ReleaseEntry};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The `.diff/Index` of an index, which lists the patches that update it incrementally.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct PdiffIndex {
+    /// The SHA256 sum and size of the index which the patches produce.
+    pub current: Option<(String, u64)>,
+    /// The sums and sizes of each past version of the index, named after the patch which
+    /// updates it.
+    pub history: Vec<ReleaseEntry>,
+    /// The sums and sizes of each uncompressed patch.
+    pub patches: Vec<ReleaseEntry>,
+    /// The sums and sizes of each compressed patch, as it is downloaded.
+    pub download: Vec<ReleaseEntry>,
+    /// Fields which are not otherwise recognized, in the order that they were defined.
+    pub extra_fields: Vec<(String, String)>,
+}
+
+impl PdiffIndex {
+    /// Reads a `.diff/Index` file, which is decompressed according to its extension.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        read_index(path).and_then(|string| string.parse::<Self>().map_err(io::Error::from))
+    }
+}
+
+impl FromStr for PdiffIndex {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut index = PdiffIndex::default();
+
+        for paragraph in paragraphs(input)? {
+            for field in &paragraph.fields {
+                match field.key {
+                    "SHA256-Current" => index.current = Some(current(field)?),
+                    "SHA256-History" => index.history = field.rows(&HashAlgorithm::Sha256)?,
+                    "SHA256-Patches" => index.patches = field.rows(&HashAlgorithm::Sha256)?,
+                    "SHA256-Download" => index.download = field.rows(&HashAlgorithm::Sha256)?,
+                    key => index
+                        .extra_fields
+                        .push((key.to_owned(), field.value.clone())),
+                }
+            }
+        }
+
+        Ok(index)
+    }
+}
+
+// The sum and size of the current index, which has no path.
+fn current(field: &Field) -> Result<(String, u64), ParseError> {
+    let mut values = field.value.split_whitespace();
+
+    let sum = values.next().unwrap_or("");
+    if let Err(reason) = HashAlgorithm::Sha256.validate(sum) {
+        return Err(ParseError::InvalidChecksum {
+            position: field.position(),
+            reason,
+        });
+    }
+
+    let size = values
+        .next()
+        .and_then(|size| size.parse::<u64>().ok())
+        .ok_or_else(|| ParseError::InvalidSize {
+            position: field.position(),
+        })?;
+
+    Ok((sum.to_owned(), size))
+}
@@ -0,0 +1,244 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A list of relations, such as a `Depends` field, where each relation is satisfied by any of
+/// its alternatives.
+pub type Relations = Vec<Vec<Relation>>;
+
+/// A single package in a relation field, such as `libc6:amd64 (>= 2.34) [amd64] <!nocheck>`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Relation {
+    pub name: String,
+    /// The architecture qualifier that follows the name, such as `any` or `native`.
+    pub arch: Option<String>,
+    pub version: Option<(VersionOp, String)>,
+    /// Architectures which the relation is restricted to. Negated architectures begin with `!`.
+    pub architectures: Vec<String>,
+    /// Each list of build profiles that the relation is restricted to.
+    pub profiles: Vec<Vec<String>>,
+}
+
+impl FromStr for Relation {
+    type Err = RelationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        let end = input
+            .find(|c: char| c.is_whitespace() || c == '(' || c == '[' || c == '<')
+            .unwrap_or(input.len());
+
+        let (name, arch) = match input[..end].find(':') {
+            Some(pos) => (&input[..pos], Some(input[pos + 1..end].to_owned())),
+            None => (&input[..end], None),
+        };
+
+        if name.is_empty() {
+            return Err(RelationError::MissingName);
+        }
+
+        let mut relation = Relation {
+            name: name.to_owned(),
+            arch,
+            version: None,
+            architectures: Vec::new(),
+            profiles: Vec::new(),
+        };
+
+        let mut rest = input[end..].trim_start();
+
+        while let Some(open) = rest.chars().next() {
+            let close = match open {
+                '(' => ')',
+                '[' => ']',
+                '<' => '>',
+                _ => return Err(RelationError::Unexpected(rest.to_owned())),
+            };
+
+            let end = rest.find(close).ok_or(RelationError::Unterminated(open))?;
+            let inner = rest[1..end].trim();
+
+            match open {
+                '(' => {
+                    let pos = inner
+                        .find(|c: char| c != '<' && c != '=' && c != '>')
+                        .unwrap_or(inner.len());
+                    let version = inner[pos..].trim();
+
+                    if version.is_empty() {
+                        return Err(RelationError::MissingVersion);
+                    }
+
+                    relation.version = Some((inner[..pos].parse()?, version.to_owned()));
+                }
+                '[' => relation
+                    .architectures
+                    .extend(inner.split_whitespace().map(String::from)),
+                _ => relation
+                    .profiles
+                    .push(inner.split_whitespace().map(String::from).collect()),
+            }
+
+            rest = rest[end + 1..].trim_start();
+        }
+
+        Ok(relation)
+    }
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.name)?;
+
+        if let Some(ref arch) = self.arch {
+            write!(fmt, ":{}", arch)?;
+        }
+
+        if let Some((op, ref version)) = self.version {
+            write!(fmt, " ({} {})", op, version)?;
+        }
+
+        if !self.architectures.is_empty() {
+            write!(fmt, " [{}]", self.architectures.join(" "))?;
+        }
+
+        for profiles in &self.profiles {
+            write!(fmt, " <{}>", profiles.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a comma-separated list of relations, with alternatives separated by `|`.
+pub fn parse_relations(input: &str) -> Result<Relations, RelationError> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|relation| !relation.is_empty())
+        .map(|relation| relation.split('|').map(str::parse).collect())
+        .collect()
+}
+
+/// The comparison that a relation makes against the version of a package.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum VersionOp {
+    /// `<<`
+    Earlier,
+    /// `<=`, or the deprecated `<`.
+    EarlierOrEqual,
+    /// `=`
+    Equal,
+    /// `>=`, or the deprecated `>`.
+    LaterOrEqual,
+    /// `>>`
+    Later,
+}
+
+impl VersionOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VersionOp::Earlier => "<<",
+            VersionOp::EarlierOrEqual => "<=",
+            VersionOp::Equal => "=",
+            VersionOp::LaterOrEqual => ">=",
+            VersionOp::Later => ">>",
+        }
+    }
+}
+
+impl FromStr for VersionOp {
+    type Err = RelationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "<<" => Ok(VersionOp::Earlier),
+            "<=" | "<" => Ok(VersionOp::EarlierOrEqual),
+            "=" => Ok(VersionOp::Equal),
+            ">=" | ">" => Ok(VersionOp::LaterOrEqual),
+            ">>" => Ok(VersionOp::Later),
+            other => Err(RelationError::InvalidOperator(other.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for VersionOp {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+/// Reasons that a relation may fail to parse.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelationError {
+    MissingName,
+    MissingVersion,
+    InvalidOperator(String),
+    /// A `(`, `[`, or `<` was not closed.
+    Unterminated(char),
+    /// Text which follows the name, but is not a version, architecture, or profile restriction.
+    Unexpected(String),
+}
+
+impl fmt::Display for RelationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RelationError::MissingName => fmt.write_str("relation is missing a package name"),
+            RelationError::MissingVersion => fmt.write_str("version constraint has no version"),
+            RelationError::InvalidOperator(ref op) => {
+                write!(fmt, "invalid version operator: '{}'", op)
+            }
+            RelationError::Unterminated(open) => write!(fmt, "'{}' is never closed", open),
+            RelationError::Unexpected(ref text) => write!(fmt, "unexpected text: {}", text),
+        }
+    }
+}
+
+impl Error for RelationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relations() {
+        let relations =
+            parse_relations("libc6 (>= 2.34), debconf (>= 0.5) | debconf-2.0, python3:any")
+                .unwrap();
+
+        assert_eq!(relations.len(), 3);
+        assert_eq!(
+            relations[0][0].version,
+            Some((VersionOp::LaterOrEqual, "2.34".into()))
+        );
+        assert_eq!(relations[1][1].name, "debconf-2.0");
+        assert_eq!(relations[2][0].arch, Some("any".into()));
+
+        let relation = "gcc-multilib (<< 1:4) [amd64 !i386] <!nocheck> <cross>"
+            .parse::<Relation>()
+            .unwrap();
+        assert_eq!(relation.version, Some((VersionOp::Earlier, "1:4".into())));
+        assert_eq!(relation.architectures, vec!["amd64", "!i386"]);
+        assert_eq!(relation.profiles, vec![vec!["!nocheck"], vec!["cross"]]);
+        assert_eq!(
+            relation.to_string(),
+            "gcc-multilib (<< 1:4) [amd64 !i386] <!nocheck> <cross>"
+        );
+
+        assert_eq!(
+            "foo (>= 1".parse::<Relation>(),
+            Err(RelationError::Unterminated('('))
+        );
+        assert_eq!(
+            "foo (=> 1)".parse::<Relation>(),
+            Err(RelationError::InvalidOperator("=>".into()))
+        );
+        assert_eq!(
+            parse_relations("foo, | bar"),
+            Err(RelationError::MissingName)
+        );
+    }
+}
@@ -0,0 +1,68 @@
+// Serde support for the types which do not derive it. Each is written as a string, as described by
+// the JSON schema in the README, so that they may be used with formats that lack `null`.
+
+use super::{Compression, HashAlgorithm, VersionOp};
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+impl Serialize for HashAlgorithm {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for HashAlgorithm {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|name| HashAlgorithm::from(name.as_str()))
+    }
+}
+
+impl Serialize for Compression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.extension().unwrap_or("none"))
+    }
+}
+
+impl<'de> Deserialize<'de> for Compression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Earlier versions wrote an uncompressed file as `null`, which is still accepted.
+        Option::<String>::deserialize(deserializer).map(|extension| match extension.as_deref() {
+            None | Some("none") => Compression::None,
+            Some(extension) => Compression::from_extension(extension),
+        })
+    }
+}
+
+impl Serialize for VersionOp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for VersionOp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse::<VersionOp>()
+            .map_err(de::Error::custom)
+    }
+}
+
+// Architectures are defined by another crate, so fields of this type name this module with
+// `#[serde(with)]`.
+pub(crate) mod architecture {
+    use deb_architectures::Architecture;
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::Serializer;
+
+    pub fn serialize<S: Serializer>(arch: &Architecture, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(<&'static str>::from(*arch))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Architecture, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse::<Architecture>()
+            .map_err(|_| de::Error::custom(format!("unknown architecture: {}", name)))
+    }
+}
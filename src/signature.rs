@@ -0,0 +1,284 @@
+use super::InRelease;
+use chrono::{DateTime, TimeZone, Utc};
+use pgp::composed::{Deserializable, DetachedSignature, SignedPublicKey, SignedPublicSubKey};
+use pgp::packet::Signature;
+use pgp::types::{KeyDetails, Timestamp};
+use std::error::Error;
+use std::path::Path;
+use std::{fmt, fs, io};
+
+/// A collection of OpenPGP public keys that release files may be verified against.
+///
+/// Keys may be loaded from binary keyrings, such as those in `/etc/apt/trusted.gpg.d/`, or from
+/// ASCII-armored key files, such as those referenced by a `Signed-By` field.
+#[derive(Debug, Default, Clone)]
+pub struct Keyring {
+    keys: Vec<SignedPublicKey>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every key from each of the given keyring files.
+    pub fn from_files<I, P>(paths: I) -> Result<Self, VerifyError>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let mut keyring = Self::new();
+        for path in paths {
+            keyring.add_file(path)?;
+        }
+
+        Ok(keyring)
+    }
+
+    /// Adds every key within a binary or armored keyring file.
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), VerifyError> {
+        fs::read(path)
+            .map_err(VerifyError::Io)
+            .and_then(|data| self.add_bytes(&data))
+    }
+
+    /// Adds every key within a binary or armored keyring.
+    pub fn add_bytes(&mut self, data: &[u8]) -> Result<(), VerifyError> {
+        let (keys, _) = SignedPublicKey::from_reader_many(data)
+            .map_err(|why| VerifyError::InvalidKeyring(why.to_string()))?;
+
+        for key in keys {
+            self.keys
+                .push(key.map_err(|why| VerifyError::InvalidKeyring(why.to_string()))?);
+        }
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Verifies a detached signature, such as a `Release.gpg` file, against the signed data.
+    ///
+    /// The signature may be either binary or ASCII-armored. Archives such as Debian's sign
+    /// their release files with several keys, so as with gpgv, the data is accepted if any of
+    /// the signatures was made by a key in the keyring and verifies. Otherwise, the error of a
+    /// signature made by a key in the keyring is preferred over that of an unknown key.
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<Signer, VerifyError> {
+        let invalid = |why: pgp::errors::Error| VerifyError::InvalidSignature(why.to_string());
+        let (signatures, _) = DetachedSignature::from_reader_many(signature).map_err(invalid)?;
+
+        let mut error = None;
+        for signature in signatures {
+            let signature = signature.map_err(invalid)?.signature;
+
+            match self.verify_signature(data, &signature) {
+                Ok(signer) => return Ok(signer),
+                Err(why) => match error {
+                    None | Some(VerifyError::UnknownKey { .. }) => error = Some(why),
+                    Some(_) => (),
+                },
+            }
+        }
+
+        Err(error.unwrap_or_else(|| VerifyError::InvalidSignature("no signatures found".into())))
+    }
+
+    fn verify_signature(&self, data: &[u8], signature: &Signature) -> Result<Signer, VerifyError> {
+        for key in &self.keys {
+            let primary_fingerprint = fingerprint(key);
+
+            if issued_by(signature, key) {
+                return check(signature, key, data, primary_fingerprint.clone(), || {
+                    key_expiration(key.primary_key.created_at(), primary_signatures(key))
+                });
+            }
+
+            for subkey in &key.public_subkeys {
+                if issued_by(signature, &subkey.key) && subkey.verify_bindings(key).is_ok() {
+                    return check(signature, &subkey.key, data, primary_fingerprint, || {
+                        subkey_expiration(subkey)
+                    });
+                }
+            }
+        }
+
+        Err(VerifyError::UnknownKey {
+            issuer: issuer(signature),
+        })
+    }
+}
+
+impl InRelease {
+    /// Verifies the clearsigned text against the embedded signature.
+    pub fn verify(&self, keyring: &Keyring) -> Result<Signer, VerifyError> {
+        // Trailing whitespace is not covered by cleartext signatures.
+        let text = self
+            .signed_text
+            .split('\n')
+            .map(|line| line.trim_end_matches([' ', '\t', '\r']))
+            .collect::<Vec<&str>>()
+            .join("\n");
+
+        keyring.verify(text.as_bytes(), &self.signature)
+    }
+}
+
+/// The key which made a valid signature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signer {
+    /// Fingerprint of the key, or subkey, that made the signature.
+    pub fingerprint: String,
+    /// Fingerprint of the primary key that the signing key belongs to.
+    pub primary_fingerprint: String,
+}
+
+/// Reasons that a signature could not be verified.
+#[derive(Debug)]
+pub enum VerifyError {
+    Io(io::Error),
+    InvalidKeyring(String),
+    InvalidSignature(String),
+    /// A key in the keyring issued the signature, but the signature does not match the data.
+    BadSignature {
+        fingerprint: String,
+    },
+    /// The signature was made by a key which has since expired.
+    ExpiredKey {
+        fingerprint: String,
+        expired: DateTime<Utc>,
+    },
+    /// None of the keys in the keyring issued the signature.
+    UnknownKey {
+        issuer: String,
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerifyError::Io(ref why) => write!(fmt, "failed to read keyring: {}", why),
+            VerifyError::InvalidKeyring(ref why) => write!(fmt, "invalid keyring: {}", why),
+            VerifyError::InvalidSignature(ref why) => write!(fmt, "invalid signature: {}", why),
+            VerifyError::BadSignature { ref fingerprint } => {
+                write!(fmt, "bad signature from key {}", fingerprint)
+            }
+            VerifyError::ExpiredKey {
+                ref fingerprint,
+                ref expired,
+            } => write!(fmt, "key {} expired on {}", fingerprint, expired),
+            VerifyError::UnknownKey { ref issuer } => {
+                write!(fmt, "signature was made by an unknown key: {}", issuer)
+            }
+        }
+    }
+}
+
+impl Error for VerifyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            VerifyError::Io(ref why) => Some(why),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for VerifyError {
+    fn from(why: io::Error) -> Self {
+        VerifyError::Io(why)
+    }
+}
+
+fn check<K, F>(
+    signature: &Signature,
+    key: &K,
+    data: &[u8],
+    primary_fingerprint: String,
+    expiration: F,
+) -> Result<Signer, VerifyError>
+where
+    K: pgp::types::VerifyingKey,
+    F: FnOnce() -> Option<DateTime<Utc>>,
+{
+    let fingerprint = fingerprint(key);
+
+    if signature.verify(key, data).is_err() {
+        return Err(VerifyError::BadSignature { fingerprint });
+    }
+
+    if let Some(expired) = expiration() {
+        if expired <= Utc::now() {
+            return Err(VerifyError::ExpiredKey {
+                fingerprint,
+                expired,
+            });
+        }
+    }
+
+    Ok(Signer {
+        fingerprint,
+        primary_fingerprint,
+    })
+}
+
+fn fingerprint<K: KeyDetails>(key: &K) -> String {
+    format!("{:X}", key.fingerprint())
+}
+
+fn issued_by<K: KeyDetails>(signature: &Signature, key: &K) -> bool {
+    let fingerprint = key.fingerprint();
+    let key_id = key.legacy_key_id();
+
+    signature.issuer_fingerprint().contains(&&fingerprint)
+        || signature.issuer_key_id().contains(&&key_id)
+}
+
+fn issuer(signature: &Signature) -> String {
+    if let Some(fingerprint) = signature.issuer_fingerprint().first() {
+        return format!("{:X}", fingerprint);
+    }
+
+    signature
+        .issuer_key_id()
+        .first()
+        .map(|id| {
+            id.as_ref()
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn primary_signatures(key: &SignedPublicKey) -> impl Iterator<Item = &Signature> {
+    key.details.direct_signatures.iter().chain(
+        key.details
+            .users
+            .iter()
+            .flat_map(|user| user.signatures.iter()),
+    )
+}
+
+fn subkey_expiration(subkey: &SignedPublicSubKey) -> Option<DateTime<Utc>> {
+    key_expiration(subkey.key.created_at(), subkey.signatures.iter())
+}
+
+// The most recent self-signature determines when a key expires.
+fn key_expiration<'a, I>(created: Timestamp, signatures: I) -> Option<DateTime<Utc>>
+where
+    I: Iterator<Item = &'a Signature>,
+{
+    signatures
+        .max_by_key(|signature| signature.created().map_or(0, Timestamp::as_secs))
+        .and_then(Signature::key_expiration_time)
+        .filter(|duration| duration.as_secs() != 0)
+        .and_then(|duration| {
+            let seconds = i64::from(created.as_secs()) + i64::from(duration.as_secs());
+            Utc.timestamp_opt(seconds, 0).single()
+        })
+}
@@ -0,0 +1,148 @@
+use super::compression::read_index;
+use super::control::{paragraphs, Paragraph};
+use super::{parse_relations, HashAlgorithm, ParseError, Relations, ReleaseEntry, ReleaseFile};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A source package index, which a `source/Sources` entry of the release file points to.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Sources {
+    pub sources: Vec<Source>,
+}
+
+impl Sources {
+    /// Reads an index from a file, which is decompressed according to its extension.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        read_index(path).and_then(|string| string.parse::<Self>().map_err(io::Error::from))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Source> {
+        self.sources.iter()
+    }
+}
+
+impl FromStr for Sources {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let sources = paragraphs(input)?
+            .iter()
+            .map(Source::from_paragraph)
+            .collect::<Result<Vec<Source>, ParseError>>()?;
+
+        Ok(Sources { sources })
+    }
+}
+
+/// A stanza of a Sources index, describing a single source package.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Source {
+    pub package: String,
+    pub version: String,
+    /// The binary packages that are built from this source package.
+    pub binary: Vec<String>,
+    pub architecture: Vec<String>,
+    pub build_depends: Relations,
+    pub build_depends_indep: Relations,
+    /// The directory of the source files, relative to the root of the repository.
+    pub directory: String,
+    /// The rows of the `Files` and `Checksums-*` fields, keyed by their algorithm.
+    ///
+    /// Paths are relative to the `directory` of the source package.
+    pub sums: BTreeMap<HashAlgorithm, Vec<ReleaseEntry>>,
+    /// Fields which are not otherwise recognized, in the order that they were defined.
+    ///
+    /// Values which span multiple lines are joined by newlines.
+    pub extra_fields: Vec<(String, String)>,
+}
+
+impl Source {
+    /// Every file of the source package, such as the `.dsc` and its tarballs, sorted by path.
+    ///
+    /// Paths are relative to the root of the repository.
+    pub fn files(&self) -> Vec<ReleaseFile> {
+        let mut files: BTreeMap<String, ReleaseFile> = BTreeMap::new();
+
+        for (algorithm, entries) in &self.sums {
+            for entry in entries {
+                let path = [&self.directory, "/", &entry.path].concat();
+
+                files
+                    .entry(path)
+                    .or_insert_with_key(|path| ReleaseFile::new(path.clone(), entry))
+                    .add(algorithm, entry);
+            }
+        }
+
+        files.into_values().collect()
+    }
+
+    fn from_paragraph(paragraph: &Paragraph) -> Result<Self, ParseError> {
+        let mut source = Source::default();
+
+        for field in &paragraph.fields {
+            let relations = || {
+                parse_relations(&field.value).map_err(|why| ParseError::InvalidValue {
+                    position: field.position(),
+                    reason: format!("{}: {}", field.key, why),
+                })
+            };
+
+            if let Some(algorithm) = checksum_algorithm(field.key) {
+                let rows = field.rows(&algorithm)?;
+                source.sums.insert(algorithm, rows);
+                continue;
+            }
+
+            match field.key {
+                "Package" => source.package = field.value.clone(),
+                "Version" => source.version = field.value.clone(),
+                "Binary" => {
+                    source.binary = field
+                        .value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|binary| !binary.is_empty())
+                        .map(String::from)
+                        .collect()
+                }
+                "Architecture" => {
+                    source.architecture = field.value.split_whitespace().map(String::from).collect()
+                }
+                "Build-Depends" => source.build_depends = relations()?,
+                "Build-Depends-Indep" => source.build_depends_indep = relations()?,
+                "Directory" => source.directory = field.value.clone(),
+                key => source
+                    .extra_fields
+                    .push((key.to_owned(), field.value.clone())),
+            }
+        }
+
+        for &key in &["Package", "Version", "Directory"] {
+            if paragraph.get(key).is_none() {
+                return Err(paragraph.missing(key));
+            }
+        }
+
+        Ok(source)
+    }
+}
+
+// The checksum method of a multi-line checksum field.
+fn checksum_algorithm(key: &str) -> Option<HashAlgorithm> {
+    match key {
+        "Files" => Some(HashAlgorithm::Md5),
+        "Checksums-Sha1" => Some(HashAlgorithm::Sha1),
+        "Checksums-Sha256" => Some(HashAlgorithm::Sha256),
+        "Checksums-Sha512" => Some(HashAlgorithm::Sha512),
+        _ => None,
+    }
+}
@@ -0,0 +1,203 @@
+use super::{
+    is_known_field, HashAlgorithm, ParseError, Position, ReleaseEntry, ReleaseEntryRef, ReleaseFile,
+};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+/// A line of a release file, as it is read by a `ReleaseReader`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReleaseEvent {
+    /// A field of the header, such as `Suite: stable`, whose value has not been interpreted.
+    Field { key: String, value: String },
+    /// The beginning of a checksum section, such as `SHA256:`.
+    Section(HashAlgorithm),
+    /// A checksum entry of the current section, with its full path.
+    Entry(HashAlgorithm, ReleaseEntry),
+}
+
+/// Receives the lines of a release file from `ReleaseReader::visit`.
+///
+/// Returning `ControlFlow::Break` from any method stops the reader at that line.
+pub trait ReleaseVisitor {
+    fn field(&mut self, _key: &str, _value: &str) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn section(&mut self, _algorithm: &HashAlgorithm) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn entry(&mut self, _algorithm: &HashAlgorithm, _entry: ReleaseEntryRef) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Reads a release file one line at a time, without holding the file or its entries in memory.
+///
+/// Checksum entries are validated in the same way as `DistRelease` validates them, but the
+/// values of header fields are passed along as they are written.
+pub struct ReleaseReader<R> {
+    reader: R,
+    line: usize,
+    buffer: String,
+    // The checksum section that entries are currently being read for.
+    section: Option<HashAlgorithm>,
+    sections: Vec<HashAlgorithm>,
+}
+
+// A borrowed event, which refers to the line that was last read.
+enum Line<'a> {
+    Field(&'a str, &'a str),
+    Section(&'a HashAlgorithm),
+    Entry(&'a HashAlgorithm, ReleaseEntryRef<'a>),
+}
+
+impl ReleaseReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        File::open(path).map(|file| ReleaseReader::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead> ReleaseReader<R> {
+    pub fn new(reader: R) -> Self {
+        ReleaseReader {
+            reader,
+            line: 0,
+            buffer: String::new(),
+            section: None,
+            sections: Vec::new(),
+        }
+    }
+
+    /// Passes each line to the visitor, until the end of the file or until the visitor breaks.
+    ///
+    /// Lines are borrowed from a single buffer, so no allocations are made for each entry.
+    pub fn visit<V: ReleaseVisitor>(&mut self, visitor: &mut V) -> io::Result<()> {
+        loop {
+            let flow = match self.read_line()? {
+                Some(Line::Field(key, value)) => visitor.field(key, value),
+                Some(Line::Section(algorithm)) => visitor.section(algorithm),
+                Some(Line::Entry(algorithm, entry)) => visitor.entry(algorithm, entry),
+                None => return Ok(()),
+            };
+
+            if flow.is_break() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Finds the checksums of the given file, relative to the `dists/<suite>/` directory.
+    ///
+    /// Every checksum section is read, but no other entries are kept.
+    pub fn find_path(mut self, path: &str) -> io::Result<Option<ReleaseFile>> {
+        struct Finder<'a> {
+            path: &'a str,
+            file: Option<ReleaseFile>,
+        }
+
+        impl<'a> ReleaseVisitor for Finder<'a> {
+            fn entry(
+                &mut self,
+                algorithm: &HashAlgorithm,
+                entry: ReleaseEntryRef,
+            ) -> ControlFlow<()> {
+                if entry.path == self.path {
+                    let entry = entry.into_owned();
+                    self.file
+                        .get_or_insert_with(|| ReleaseFile::new(entry.path.clone(), &entry))
+                        .add(algorithm, &entry);
+                }
+
+                ControlFlow::Continue(())
+            }
+        }
+
+        let mut finder = Finder { path, file: None };
+        self.visit(&mut finder)?;
+        Ok(finder.file)
+    }
+
+    // Reads the next line which is not blank, and parses it.
+    fn read_line(&mut self) -> io::Result<Option<Line<'_>>> {
+        loop {
+            self.buffer.clear();
+            if self.reader.read_line(&mut self.buffer)? == 0 {
+                return Ok(None);
+            }
+
+            self.line += 1;
+            if !self.buffer.trim().is_empty() {
+                break;
+            }
+        }
+
+        let number = self.line;
+        let line = self.buffer.trim_end_matches(['\n', '\r']);
+        let position = |span| Position::new(number, line, span);
+
+        if line.starts_with(' ') || line.starts_with('\t') {
+            return match self.section {
+                Some(ref algorithm) => {
+                    let entry = ReleaseEntryRef::parse_row(algorithm, number, line)?;
+                    Ok(Some(Line::Entry(algorithm, entry)))
+                }
+                None => Err(ParseError::EntryOutsideSection {
+                    position: position(0..line.len()),
+                }
+                .into()),
+            };
+        }
+
+        let (key, value) = match line.find(':') {
+            Some(pos) => (&line[..pos], line[pos + 1..].trim()),
+            None => {
+                return Err(ParseError::UnknownKey {
+                    position: position(0..line.len()),
+                }
+                .into())
+            }
+        };
+
+        // A known field with an empty value is still a field, as it is for `DistReleaseRef`.
+        if !value.is_empty() || is_known_field(key) {
+            self.section = None;
+            return Ok(Some(Line::Field(key, value)));
+        }
+
+        let algorithm = HashAlgorithm::from(key);
+        if self.sections.contains(&algorithm) {
+            return Err(ParseError::DuplicateField {
+                position: position(0..key.len()),
+                field: key.to_owned(),
+            }
+            .into());
+        }
+
+        self.sections.push(algorithm.clone());
+        Ok(Some(Line::Section(self.section.insert(algorithm))))
+    }
+}
+
+impl<R: BufRead> Iterator for ReleaseReader<R> {
+    type Item = io::Result<ReleaseEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = match self.read_line() {
+            Ok(Some(Line::Field(key, value))) => ReleaseEvent::Field {
+                key: key.to_owned(),
+                value: value.to_owned(),
+            },
+            Ok(Some(Line::Section(algorithm))) => ReleaseEvent::Section(algorithm.clone()),
+            Ok(Some(Line::Entry(algorithm, entry))) => {
+                ReleaseEvent::Entry(algorithm.clone(), entry.into_owned())
+            }
+            Ok(None) => return None,
+            Err(why) => return Some(Err(why)),
+        };
+
+        Some(Ok(event))
+    }
+}
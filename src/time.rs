@@ -1,15 +1,15 @@
 use chrono::{DateTime, Utc};
-use std::io;
 
-pub(crate) fn get_time(value: &str) -> io::Result<DateTime<Utc>> {
+/// The RFC 2822 date format that apt writes into the `Date` field of a release file, which
+/// spells the zone as `UTC` rather than `+0000`.
+pub(crate) const DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S UTC";
+
+pub(crate) fn get_time(value: &str) -> Result<DateTime<Utc>, String> {
     let fields = value.split_whitespace().collect::<Vec<&str>>();
     if fields.len() != 6 {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!(
-                "timezone is invalid: should have been six fields: {}",
-                value
-            ),
+        return Err(format!(
+            "timezone is invalid: should have been six fields: {}",
+            value
         ));
     }
 
@@ -20,23 +20,18 @@ pub(crate) fn get_time(value: &str) -> io::Result<DateTime<Utc>> {
         buffer.push('0');
     }
 
-    buffer.push_str(&fields[4]);
+    buffer.push_str(fields[4]);
     buffer.push(' ');
 
     if fields[5] == "UTC" {
         buffer.push_str("+0000");
     } else {
-        buffer.push_str(&fields[5])
+        buffer.push_str(fields[5])
     };
 
     DateTime::parse_from_rfc2822(&buffer)
         .map(|tz| tz.with_timezone(&Utc))
-        .map_err(|why| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("unable to parse date ({}) in release file: {}", buffer, why),
-            )
-        })
+        .map_err(|why| format!("unable to parse date ({}) in release file: {}", buffer, why))
 }
 
 #[cfg(test)]
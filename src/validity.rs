@@ -0,0 +1,95 @@
+use super::DistRelease;
+use chrono::{DateTime, Duration, Utc};
+
+/// The settings which apt uses to decide whether a release file is too old, or too new, to be
+/// trusted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidityPolicy {
+    /// `Acquire::Check-Valid-Until`: whether release files expire at all.
+    pub check_valid_until: bool,
+    /// `Acquire::Max-ValidTime`: release files expire this long after their `Date`, or at
+    /// their `Valid-Until` if it is sooner.
+    pub max_valid_time: Option<Duration>,
+    /// `Acquire::Min-ValidTime`: release files remain valid for at least this long after their
+    /// `Date`, even if their `Valid-Until` is sooner.
+    pub min_valid_time: Option<Duration>,
+    /// `Acquire::Check-Date`: whether release files dated in the future are rejected.
+    pub check_date: bool,
+    /// `Acquire::Max-FutureTime`: how far into the future a `Date` may be, to tolerate clocks
+    /// which are out of sync.
+    pub max_future_time: Duration,
+}
+
+impl Default for ValidityPolicy {
+    /// The defaults of apt.
+    fn default() -> Self {
+        ValidityPolicy {
+            check_valid_until: true,
+            max_valid_time: None,
+            min_valid_time: None,
+            check_date: true,
+            max_future_time: Duration::seconds(10),
+        }
+    }
+}
+
+/// Whether apt would accept a release file at a given time.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Validity {
+    /// The release file is valid, until the given time if it expires.
+    Valid { until: Option<DateTime<Utc>> },
+    /// The release file expired at the given time.
+    Expired { since: DateTime<Utc> },
+    /// The `Date` of the release file is further in the future than the policy tolerates.
+    NotYetValid { date: DateTime<Utc> },
+}
+
+impl Validity {
+    pub fn is_valid(&self) -> bool {
+        matches!(*self, Validity::Valid { .. })
+    }
+}
+
+impl DistRelease {
+    /// The time that the release file expires under the given policy, if it expires at all.
+    ///
+    /// As in apt, `Min-ValidTime` extends a `Valid-Until` which would expire sooner, and then
+    /// `Max-ValidTime` caps the result, whether or not there is a `Valid-Until`.
+    pub fn expires(&self, policy: &ValidityPolicy) -> Option<DateTime<Utc>> {
+        if !policy.check_valid_until {
+            return None;
+        }
+
+        let mut until = self.valid_until;
+
+        if let (Some(valid_until), Some(date), Some(min)) =
+            (until, self.date, policy.min_valid_time)
+        {
+            until = Some(valid_until.max(date + min));
+        }
+
+        if let (Some(date), Some(max)) = (self.date, policy.max_valid_time) {
+            let max = date + max;
+            until = Some(until.map_or(max, |until| until.min(max)));
+        }
+
+        until
+    }
+
+    /// Checks the `Date` and `Valid-Until` fields against the current time, following the
+    /// rules that apt uses to refuse stale release files.
+    pub fn check_validity(&self, now: DateTime<Utc>, policy: &ValidityPolicy) -> Validity {
+        if policy.check_date {
+            if let Some(date) = self.date {
+                if date > now + policy.max_future_time {
+                    return Validity::NotYetValid { date };
+                }
+            }
+        }
+
+        match self.expires(policy) {
+            Some(until) if until < now => Validity::Expired { since: until },
+            until => Validity::Valid { until },
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use super::entry::entry_variant;
+use super::hash::Hasher;
+use super::{Compression, DistRelease, EntryVariant, HashAlgorithm};
+use std::fs::File;
+use std::path::Path;
+use std::{fmt, io};
+
+/// A file listed in the release file which does not match the local copy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    /// The file does not exist.
+    Missing { path: String },
+    /// The size of the file differs from the release file.
+    Size {
+        path: String,
+        expected: u64,
+        found: u64,
+    },
+    /// The digest of the file differs from the release file.
+    Checksum {
+        path: String,
+        algorithm: HashAlgorithm,
+        expected: String,
+        found: String,
+    },
+}
+
+impl Mismatch {
+    /// The path of the file, relative to the `dists/<suite>/` directory.
+    pub fn path(&self) -> &str {
+        match *self {
+            Mismatch::Missing { ref path }
+            | Mismatch::Size { ref path, .. }
+            | Mismatch::Checksum { ref path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Mismatch::Missing { ref path } => write!(fmt, "{}: missing", path),
+            Mismatch::Size {
+                ref path,
+                expected,
+                found,
+            } => write!(
+                fmt,
+                "{}: expected {} bytes, but found {}",
+                path, expected, found
+            ),
+            Mismatch::Checksum {
+                ref path,
+                ref algorithm,
+                ref expected,
+                ref found,
+            } => write!(
+                fmt,
+                "{}: expected {} {}, but found {}",
+                path, algorithm, expected, found
+            ),
+        }
+    }
+}
+
+impl DistRelease {
+    /// Checks every file in the release file against the local `dists/<suite>/` directory.
+    ///
+    /// Each file is checked with the strongest checksum that the release file lists for it.
+    /// Files which are missing, or have the wrong size or digest, are returned.
+    ///
+    /// Archives such as Debian's list the uncompressed forms of indices without publishing them,
+    /// so a missing uncompressed index is not reported if another form of it is present and
+    /// matches.
+    pub fn verify_dir<P: AsRef<Path>>(&self, dir: P) -> io::Result<Vec<Mismatch>> {
+        let dir = dir.as_ref();
+        let mut mismatches = Vec::new();
+        let mut matched: Vec<EntryVariant> = Vec::new();
+
+        for file in self.files() {
+            let (algorithm, sum) = match file.strongest_sum() {
+                Some(strongest) => strongest,
+                None => continue,
+            };
+
+            let path = file.path.clone();
+
+            let local = match File::open(dir.join(&path)) {
+                Ok(local) => local,
+                Err(ref why) if why.kind() == io::ErrorKind::NotFound => {
+                    mismatches.push(Mismatch::Missing { path });
+                    continue;
+                }
+                Err(why) => return Err(why),
+            };
+
+            let size = local.metadata()?.len();
+            if size != file.size {
+                mismatches.push(Mismatch::Size {
+                    path,
+                    expected: file.size,
+                    found: size,
+                });
+                continue;
+            }
+
+            let mut hasher = Hasher::new(algorithm).expect("algorithm is known");
+            hasher.consume(local)?;
+            let found = hasher.finish();
+
+            if !found.eq_ignore_ascii_case(sum) {
+                mismatches.push(Mismatch::Checksum {
+                    path,
+                    algorithm: algorithm.clone(),
+                    expected: sum.to_owned(),
+                    found,
+                });
+            } else if let Some(variant) = file.variant() {
+                matched.push(variant);
+            }
+        }
+
+        mismatches.retain(|mismatch| {
+            let variant = match *mismatch {
+                Mismatch::Missing { ref path } => entry_variant(path),
+                _ => None,
+            };
+
+            match variant {
+                Some(ref variant) if variant.compression() == Some(&Compression::None) => {
+                    !matched.iter().any(|other| other.is_same_index(variant))
+                }
+                _ => true,
+            }
+        });
+
+        Ok(mismatches)
+    }
+}
@@ -1,10 +1,1307 @@
 extern crate apt_release_file;
+extern crate bzip2;
+extern crate chrono;
 extern crate deb_architectures;
+extern crate flate2;
+extern crate lz4_flex;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+extern crate xz2;
+extern crate zstd;
 
 use apt_release_file::{
-    BinaryEntry, Dep11Entry, EntryVariant, I18nEntry, ImageSize, ReleaseEntry, SourceEntry,
+    BinaryEntry, ByHashEntry, ChecksumError, Compression, ContentsEntry, ContentsReader,
+    DebianInstallerEntry, Dep11Entry, Digest, DistRelease, DistReleaseRef, EntryError,
+    EntryVariant, FileChange, HashAlgorithm, I18nEntry, ImageSize, InRelease, InReleaseError,
+    IndexReader, Mismatch, Packages, ParseError, PdiffError, PdiffIndex, ReleaseBuilder,
+    ReleaseEntry, ReleaseEntryRef, ReleaseEvent, ReleaseReader, ReleaseVisitor, SourceEntry,
+    Sources, Validity, ValidityPolicy, VersionOp,
 };
+use bzip2::write::BzEncoder;
 use deb_architectures::Architecture;
+use flate2::write::GzEncoder;
+use lz4_flex::frame::FrameEncoder;
+use std::io::{BufReader, Read, Write};
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::{fs, io};
+use xz2::write::XzEncoder;
+
+const RELEASE: &str = include_str!("Release");
+
+#[test]
+fn release_round_trip() {
+    let release = RELEASE.parse::<DistRelease>().unwrap();
+    assert_eq!(release.to_string(), RELEASE);
+    assert_eq!(
+        release.sums.keys().collect::<Vec<_>>(),
+        vec![
+            &HashAlgorithm::Md5,
+            &HashAlgorithm::Sha1,
+            &HashAlgorithm::Sha256,
+            &HashAlgorithm::Sha512
+        ]
+    );
+
+    // Sections keep their order, and sections without any entries are kept.
+    const SECTIONS: &str = "Suite: stable
+SHA256:
+ ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad                3 Contents-amd64
+SHA1:
+MD5Sum:
+ 900150983cd24fb0d6963f7d28e17f72                3 Contents-amd64
+";
+
+    let release = SECTIONS.parse::<DistRelease>().unwrap();
+    assert_eq!(
+        release.sections,
+        vec![
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Sha1,
+            HashAlgorithm::Md5
+        ]
+    );
+    assert!(release.sums[&HashAlgorithm::Sha1].is_empty());
+    assert_eq!(release.to_string(), SECTIONS);
+}
+
+#[test]
+fn release_round_trip_debian_order() {
+    const INPUT: &str = "Origin: Debian
+Label: Debian
+Suite: stable
+Version: 12.7
+Codename: bookworm
+Changed-By: Debian FTP Masters <ftpmaster@ftp-master.debian.org>
+Date: Sat, 31 Aug 2024 10:19:20 UTC
+Acquire-By-Hash: yes
+No-Support-for-Architecture-all: Packages
+Architectures: all amd64 arm64
+Components: main contrib
+Description: Debian 12.7 Released 31 August 2024
+MD5Sum:
+ 0d3a31af2bf9ab78412cbc774364ed6c         45568921 main/binary-amd64/Packages
+ de54075fa3c455ebea0f2dd21f23f6b1          9436732 main/binary-amd64/Packages.xz
+ b24a827c75719e5353bed7c85a0ca11d        587316042 main/Contents-amd64
+ 2b0a31731c80117890bd45ba0b173a4f         42179331 main/Contents-amd64.gz
+ a949ff0d7c08bb7e09c53000b8298113           259463 contrib/binary-all/Packages
+ a8c7d2473de25805409ecafef0345eaf             1233 Contents-all
+SHA256:
+ a5fcac504951e1ad698631619f02a0e9575cdfc64226106c3083986c5bc80b84         45568921 main/binary-amd64/Packages
+ cc1bc17965c91fb1034bd334d1ff78b081c4539b986e987d407305a3061b8f2e          9436732 main/binary-amd64/Packages.xz
+ cf5bab499bef092025c97ecd92aa30b69ad9334d0f19b1f4c083cf6d169e0a40        587316042 main/Contents-amd64
+ 3e4e22bbf5364ffce917bbcf1fae7be23203053ca443b0ede80038c134e66272         42179331 main/Contents-amd64.gz
+ 8cea8110603bab9d4f312b06eb5f2d157641c38b94ef633b6c357ff5c945f579           259463 contrib/binary-all/Packages
+ a16b971db82893a59903d4130cf967f1ea6f28609ee7e4e0bb59bd78a4ff0cb6             1233 Contents-all
+";
+
+    let mut release = INPUT.parse::<DistRelease>().unwrap();
+    assert_eq!(release.to_string(), INPUT);
+
+    // Changed fields keep their position, and fields defined in code follow.
+    release.version = Some("12.8".into());
+    release.valid_until = release.date.map(|date| date + chrono::Duration::days(7));
+    release.acquire_by_hash = None;
+
+    let written = release.to_string();
+    assert!(written.starts_with(
+        "Origin: Debian
+Label: Debian
+Suite: stable
+Version: 12.8
+Codename: bookworm
+Changed-By: Debian FTP Masters <ftpmaster@ftp-master.debian.org>
+Date: Sat, 31 Aug 2024 10:19:20 UTC
+No-Support-for-Architecture-all: Packages
+Architectures: all amd64 arm64
+Components: main contrib
+Description: Debian 12.7 Released 31 August 2024
+Valid-Until: Sat, 07 Sep 2024 10:19:20 UTC
+MD5Sum:
+"
+    ));
+}
+
+#[test]
+fn release_optional_fields() {
+    const INPUT: &str = "Acquire-By-Hash: yes
+Architectures: amd64 arm64
+ButAutomaticUpgrades: no
+Changelogs: https://metadata.ftp-master.debian.org/changelogs/@CHANGEPATH@_changelog
+Codename: bookworm-backports
+Components: main contrib
+Changed-By: Debian Backports Team <ftpmaster@backports.debian.org>
+Date: Sat, 14 Sep 2024 08:12:47 +0000
+Description: Debian 12 Backports
+Label: Debian Backports
+No-Support-for-Architecture-all: Packages
+NotAutomatic: yes
+Origin: Debian Backports
+Signed-By: 11CBFB4112831CED41A0278D63271E7B02EC409A 7E92CA7162A7F2AEFD3EE9AC27D138A21F8B9FEE
+Suite: bookworm-backports
+X-Custom: preserved
+Valid-Until: Sat, 21 Sep 2024 08:12:47 +0000
+Version: 12
+X-Other: also preserved
+MD5Sum:
+ ef4374f4a7eb9dc65bb51234fce91247         39115618 main/Contents-arm64.gz
+";
+
+    let release = INPUT.parse::<DistRelease>().unwrap();
+    assert_eq!(release.acquire_by_hash, Some(true));
+    assert_eq!(release.but_automatic_upgrades, Some(false));
+    assert_eq!(release.not_automatic, Some(true));
+    assert_eq!(
+        release.no_support_for_architecture_all,
+        Some("Packages".into())
+    );
+    assert_eq!(release.signed_by.as_ref().map(Vec::len), Some(2));
+    assert_eq!(
+        release.valid_until.map(|date| date.to_rfc3339()),
+        Some("2024-09-21T08:12:47+00:00".into())
+    );
+    assert_eq!(
+        release.extra_fields().collect::<Vec<_>>(),
+        vec![
+            (
+                "Changed-By",
+                "Debian Backports Team <ftpmaster@backports.debian.org>"
+            ),
+            ("X-Custom", "preserved"),
+            ("X-Other", "also preserved")
+        ]
+    );
+    assert_eq!(release.to_string(), INPUT);
+
+    // Unknown fields stay between the known fields around them when those are removed.
+    let mut release = release;
+    release.valid_until = None;
+    assert_eq!(
+        release.to_string(),
+        INPUT.replace("Valid-Until: Sat, 21 Sep 2024 08:12:47 +0000\n", "")
+    );
+}
+
+#[test]
+fn release_partial_header() {
+    const INPUT: &str = "Suite: stable
+Origin: LP-PPA-example
+Label: example PPA
+Architectures: amd64
+";
+
+    let release = INPUT.parse::<DistRelease>().unwrap();
+    assert_eq!(release.suite, Some("stable".into()));
+    assert_eq!(release.origin, Some("LP-PPA-example".into()));
+    assert_eq!(release.architectures, Some(vec!["amd64".into()]));
+    assert_eq!(release.codename, None);
+    assert_eq!(release.date, None);
+    assert_eq!(release.version, None);
+    assert!(release.sums.is_empty());
+
+    let release = "Codename: cosmic\nMD5Sum:\n 3439b462cea992ac689c9047ce7a5463 3079744 Contents-all\nSuite: cosmic"
+        .parse::<DistRelease>()
+        .unwrap();
+    assert_eq!(release.codename, Some("cosmic".into()));
+    assert_eq!(release.suite, Some("cosmic".into()));
+    assert_eq!(
+        release.sums[&HashAlgorithm::Md5].base["Contents-all"].len(),
+        1
+    );
+
+    assert!(" 3439b462cea992ac689c9047ce7a5463 3079744 Contents-all"
+        .parse::<DistRelease>()
+        .is_err());
+    assert!("".parse::<DistRelease>().is_ok());
+}
+
+#[test]
+fn release_parse_errors() {
+    fn error(input: &str) -> ParseError {
+        input.parse::<DistRelease>().unwrap_err()
+    }
+
+    let why = error("Codename: cosmic\nDate: Tue, 06 Nov 2018\n");
+    match why {
+        ParseError::InvalidDate { ref position, .. } => {
+            assert_eq!(position.line, 2);
+            assert_eq!(position.text, "Date: Tue, 06 Nov 2018");
+            assert_eq!(position.fragment(), "Tue, 06 Nov 2018");
+        }
+        ref other => panic!("expected an invalid date: {:?}", other),
+    }
+    assert!(why.to_string().starts_with("line 2: invalid date: "));
+
+    match error("MD5Sum:\n 3439b462cea992ac689c9047ce7a5463 30797x44 Contents-all\n") {
+        ParseError::InvalidSize { position } => {
+            assert_eq!(position.line, 2);
+            assert_eq!(position.span, 34..42);
+            assert_eq!(position.fragment(), "30797x44");
+        }
+        other => panic!("expected an invalid size: {:?}", other),
+    }
+
+    match error("MD5Sum:\n 3439b462cea992ac689c9047ce7a5463 3079744\n") {
+        ParseError::InvalidEntry { position, reason } => {
+            assert_eq!(position.line, 2);
+            assert_eq!(reason, EntryError::MissingPath);
+        }
+        other => panic!("expected an invalid entry: {:?}", other),
+    }
+
+    match error("Suite: cosmic\n\nCodename: cosmic\nSuite: disco\n") {
+        ParseError::DuplicateField { position, field } => {
+            assert_eq!(position.line, 4);
+            assert_eq!(position.fragment(), "Suite");
+            assert_eq!(field, "Suite");
+        }
+        other => panic!("expected a duplicate field: {:?}", other),
+    }
+
+    match error("Suite: cosmic\nnonsense\n") {
+        ParseError::UnknownKey { position } => assert_eq!(position.line, 2),
+        other => panic!("expected an unknown key: {:?}", other),
+    }
+
+    match error("SHA256:\n 3439b462cea992ac689c9047ce7a5463 3079744 Contents-all\n") {
+        ParseError::InvalidChecksum { position, reason } => {
+            assert_eq!(position.span, 1..33);
+            assert_eq!(
+                reason,
+                ChecksumError::InvalidLength {
+                    expected: 64,
+                    found: 32
+                }
+            );
+        }
+        other => panic!("expected an invalid checksum: {:?}", other),
+    }
+
+    match error("NotAutomatic: maybe\n") {
+        ParseError::InvalidValue { position, .. } => assert_eq!(position.fragment(), "maybe"),
+        other => panic!("expected an invalid value: {:?}", other),
+    }
+}
+
+#[test]
+fn release_entry_digests() {
+    let release = RELEASE.parse::<DistRelease>().unwrap();
+
+    for (algorithm, components) in &release.sums {
+        for (_, entry) in components.iter() {
+            let decoded = entry.decode(algorithm).unwrap();
+            assert_eq!(
+                decoded.sum.as_bytes().unwrap().len(),
+                algorithm.digest_len().unwrap()
+            );
+            assert_eq!(&ReleaseEntry::from(decoded), entry);
+        }
+    }
+
+    // Decoded sums are the same regardless of the case of their hex digits.
+    let entry = |sum: &str| ReleaseEntry {
+        sum: sum.into(),
+        size: 3,
+        path: "Contents-all".into(),
+    };
+    let lower = entry("900150983cd24fb0d6963f7d28e17f72");
+    let upper = entry("900150983CD24FB0D6963F7D28E17F72");
+    assert_eq!(
+        lower.decode(&HashAlgorithm::Md5).unwrap(),
+        upper.decode(&HashAlgorithm::Md5).unwrap()
+    );
+    assert_eq!(
+        lower.decode(&HashAlgorithm::Sha1).unwrap_err(),
+        ChecksumError::InvalidLength {
+            expected: 40,
+            found: 32
+        }
+    );
+
+    let unknown = HashAlgorithm::Unknown("CRC32".into());
+    let decoded = entry("not hex").decode(&unknown).unwrap();
+    assert_eq!(decoded.sum, Digest::Unknown("not hex".into()));
+    assert_eq!(decoded.sum.to_string(), "not hex");
+}
+
+#[test]
+fn release_files() {
+    let release = RELEASE.parse::<DistRelease>().unwrap();
+    let files = release.files();
+    assert_eq!(files.len(), 24);
+    assert!(files
+        .iter()
+        .all(|file| file.sums.len() == 4 && !file.size_mismatch));
+
+    let file = release.get("main/binary-amd64/Packages.xz").unwrap();
+    assert_eq!(file.size, 50360);
+    assert_eq!(
+        file.sums[&HashAlgorithm::Md5],
+        "e3f31573c9b7f17599b631b36a520cfa"
+    );
+    assert!(files.contains(&file));
+    assert_eq!(
+        file.strongest_sum().map(|(algorithm, _)| algorithm),
+        Some(&HashAlgorithm::Sha512)
+    );
+
+    assert!(release.get("Contents-all").is_some());
+    assert!(release.get("main/binary-amd64/Packages.bz2").is_none());
+    assert!(release.get("restricted/binary-amd64/Packages").is_none());
+
+    let release = "MD5Sum:
+ 900150983cd24fb0d6963f7d28e17f72                3 main/source/Sources
+SHA256:
+ ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad                4 main/source/Sources
+"
+    .parse::<DistRelease>()
+    .unwrap();
+
+    let file = release.get("main/source/Sources").unwrap();
+    assert!(file.size_mismatch);
+    assert_eq!(file.size, 4);
+
+    // Sections which cannot be checked are ordered last, but do not decide the size.
+    let release = "SHA256:
+ ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad                3 main/source/Sources
+SHA3-256:
+ 3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532                5 main/source/Sources
+"
+    .parse::<DistRelease>()
+    .unwrap();
+
+    let file = release.get("main/source/Sources").unwrap();
+    assert!(!file.size_mismatch);
+    assert_eq!(file.size, 3);
+    assert_eq!(file.sums.len(), 2);
+    assert_eq!(release.files(), vec![file]);
+}
+
+#[test]
+fn release_borrowed() {
+    let borrowed = DistReleaseRef::parse(RELEASE).unwrap();
+
+    let codename = borrowed.codename.unwrap();
+    let offset = codename.as_ptr() as usize - RELEASE.as_ptr() as usize;
+    assert_eq!(&RELEASE[offset..offset + codename.len()], codename);
+
+    let entry = borrowed.sums[&HashAlgorithm::Md5]
+        .get("main/binary-amd64/Packages.xz")
+        .unwrap();
+    assert_eq!(entry.sum, "e3f31573c9b7f17599b631b36a520cfa");
+    assert_eq!(entry.size, 50360);
+    assert_eq!(entry.path, "binary-amd64/Packages.xz");
+    assert_eq!(
+        borrowed.sums[&HashAlgorithm::Md5].iter().count(),
+        RELEASE.parse::<DistRelease>().unwrap().files().len()
+    );
+
+    assert_eq!(
+        borrowed.into_owned(),
+        RELEASE.parse::<DistRelease>().unwrap()
+    );
+
+    match DistReleaseRef::parse("SHA256:\n 1234 3 main/source/Sources\n") {
+        Err(ParseError::InvalidChecksum { position, .. }) => assert_eq!(position.line, 2),
+        other => panic!("expected an invalid checksum: {:?}", other),
+    }
+}
+
+#[test]
+fn release_stream() {
+    let release = RELEASE.parse::<DistRelease>().unwrap();
+
+    let events = ReleaseReader::new(RELEASE.as_bytes())
+        .collect::<io::Result<Vec<ReleaseEvent>>>()
+        .unwrap();
+    assert_eq!(
+        events[0],
+        ReleaseEvent::Field {
+            key: "Architectures".into(),
+            value: "i386 amd64 all".into()
+        }
+    );
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| matches!(event, ReleaseEvent::Section(_)))
+            .count(),
+        4
+    );
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| matches!(event, ReleaseEvent::Entry(..)))
+            .count(),
+        4 * release.files().len()
+    );
+
+    // Indices are often read through a decompressor.
+    let compressed = compress(&Compression::Gzip, RELEASE.as_bytes());
+    let reader = BufReader::new(Compression::Gzip.decoder(&compressed[..]).unwrap());
+    assert_eq!(
+        ReleaseReader::new(reader)
+            .find_path("main/binary-amd64/Packages.xz")
+            .unwrap(),
+        release.get("main/binary-amd64/Packages.xz")
+    );
+    assert_eq!(
+        ReleaseReader::new(RELEASE.as_bytes())
+            .find_path("main/binary-amd64/Packages.bz2")
+            .unwrap(),
+        None
+    );
+
+    struct FirstEntry(Option<(HashAlgorithm, ReleaseEntry)>);
+
+    impl ReleaseVisitor for FirstEntry {
+        fn entry(&mut self, algorithm: &HashAlgorithm, entry: ReleaseEntryRef) -> ControlFlow<()> {
+            self.0 = Some((algorithm.clone(), entry.into_owned()));
+            ControlFlow::Break(())
+        }
+    }
+
+    let mut reader = ReleaseReader::new(RELEASE.as_bytes());
+    let mut first = FirstEntry(None);
+    reader.visit(&mut first).unwrap();
+    let (algorithm, entry) = first.0.unwrap();
+    assert_eq!(algorithm, HashAlgorithm::Md5);
+    assert_eq!(entry.path, "Contents-all");
+    assert!(matches!(
+        reader.next(),
+        Some(Ok(ReleaseEvent::Entry(HashAlgorithm::Md5, _)))
+    ));
+
+    let error = ReleaseReader::new(&b"MD5Sum:\n\nMD5Sum:\n"[..])
+        .find(Result::is_err)
+        .unwrap()
+        .unwrap_err();
+    match error
+        .get_ref()
+        .and_then(|why| why.downcast_ref::<ParseError>())
+    {
+        Some(ParseError::DuplicateField { position, .. }) => assert_eq!(position.line, 3),
+        other => panic!("expected a duplicate section: {:?}", other),
+    }
+
+    // An empty known field is not mistaken for a checksum section.
+    let events = ReleaseReader::new(&b"Description:\nSHA256:\n"[..])
+        .collect::<io::Result<Vec<ReleaseEvent>>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            ReleaseEvent::Field {
+                key: "Description".into(),
+                value: "".into()
+            },
+            ReleaseEvent::Section(HashAlgorithm::Sha256),
+        ]
+    );
+}
+
+#[test]
+fn release_diff() {
+    let old = RELEASE.parse::<DistRelease>().unwrap();
+    assert!(DistRelease::diff(&old, &old).is_empty());
+
+    // Known fields which are only written differently are unchanged.
+    let rewritten = RELEASE
+        .replace(
+            "Date: Tue, 06 Nov 2018 14:01:53 +0000",
+            "Date: Tue, 6 Nov 2018 14:01:53 UTC",
+        )
+        .replace(
+            "Architectures: i386 amd64 all",
+            "Architectures: i386  amd64 all",
+        )
+        .parse::<DistRelease>()
+        .unwrap();
+    assert_ne!(rewritten.fields, old.fields);
+    assert!(DistRelease::diff(&old, &rewritten).is_empty());
+
+    let mut new = old.clone();
+    new.version = Some("18.10.1".into());
+    new.valid_until = Some(old.date.unwrap() + chrono::Duration::days(7));
+
+    // Dropping a checksum section does not change the files that remain.
+    new.sums.remove(&HashAlgorithm::Md5);
+    for components in new.sums.values_mut() {
+        components.base.remove("Contents-i386");
+    }
+
+    let sha256 = new.sums.get_mut(&HashAlgorithm::Sha256).unwrap();
+    for entry in sha256
+        .components
+        .get_mut("main")
+        .unwrap()
+        .get_mut("main/binary-amd64/Packages")
+        .unwrap()
+    {
+        if entry.path == "binary-amd64/Packages.xz" {
+            entry.sum = "0".repeat(64);
+        }
+    }
+
+    sha256.insert(ReleaseEntry {
+        sum: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".into(),
+        size: 3,
+        path: "main/i18n/Translation-en.xz".into(),
+    });
+
+    let diff = DistRelease::diff(&old, &new);
+    assert_eq!(
+        diff.fields
+            .iter()
+            .map(|field| (
+                field.key.as_str(),
+                field.old.as_deref(),
+                field.new.as_deref()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("Version", Some("18.10"), Some("18.10.1")),
+            ("Valid-Until", None, Some("Tue, 13 Nov 2018 14:01:53 UTC")),
+        ]
+    );
+
+    // Every compressed form of an index is in the same group.
+    match diff.components[&None][..] {
+        [(Some(ref variant), ref files)] => {
+            assert_eq!(
+                variant,
+                &EntryVariant::Contents(Architecture::I386, Compression::None)
+            );
+            assert!(files
+                .iter()
+                .all(|change| matches!(change, FileChange::Removed(_))));
+            assert_eq!(files.len(), 3);
+        }
+        ref other => panic!("expected a single removed index: {:?}", other),
+    }
+
+    match diff.components[&Some("main".to_owned())][..] {
+        [(Some(ref packages), ref changed), (Some(ref translations), ref added)] => {
+            assert_eq!(
+                packages,
+                &EntryVariant::Binary(
+                    BinaryEntry::Packages(Compression::None),
+                    Architecture::Amd64
+                )
+            );
+            assert_eq!(
+                translations,
+                &EntryVariant::I18n(I18nEntry::Translations("en".into(), Compression::None))
+            );
+
+            match (&changed[..], &added[..]) {
+                ([FileChange::Changed { old, new }], [FileChange::Added(added)]) => {
+                    assert_eq!(new.path, "main/binary-amd64/Packages.xz");
+                    assert_eq!(new.sums[&HashAlgorithm::Sha256], "0".repeat(64));
+                    assert_ne!(old.sums[&HashAlgorithm::Sha256], "0".repeat(64));
+                    assert_eq!(added.path, "main/i18n/Translation-en.xz");
+                }
+                other => panic!("expected a changed and an added file: {:?}", other),
+            }
+        }
+        ref other => panic!("expected two changed indices: {:?}", other),
+    }
+
+    assert_eq!(
+        diff.updated_files()
+            .map(|file| file.path.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "main/binary-amd64/Packages.xz",
+            "main/i18n/Translation-en.xz"
+        ]
+    );
+    assert_eq!(
+        diff.indices(),
+        vec![
+            EntryVariant::Contents(Architecture::I386, Compression::None),
+            EntryVariant::Binary(
+                BinaryEntry::Packages(Compression::None),
+                Architecture::Amd64
+            ),
+            EntryVariant::I18n(I18nEntry::Translations("en".into(), Compression::None)),
+        ]
+    );
+
+    assert_eq!(
+        diff.to_string(),
+        "- Version: 18.10
++ Version: 18.10.1
++ Valid-Until: Tue, 13 Nov 2018 14:01:53 UTC
+- Contents-i386
+- Contents-i386.gz
+- Contents-i386.xz
+~ main/binary-amd64/Packages.xz
++ main/i18n/Translation-en.xz
+"
+    );
+}
+
+#[test]
+fn release_validity() {
+    use chrono::{Duration, TimeZone, Utc};
+
+    let release = "Date: Tue, 06 Nov 2018 14:01:53 UTC
+Valid-Until: Tue, 13 Nov 2018 14:01:53 UTC
+"
+    .parse::<DistRelease>()
+    .unwrap();
+
+    let date = Utc.with_ymd_and_hms(2018, 11, 6, 14, 1, 53).unwrap();
+    let valid_until = Utc.with_ymd_and_hms(2018, 11, 13, 14, 1, 53).unwrap();
+    let policy = ValidityPolicy::default();
+
+    assert_eq!(
+        release.check_validity(date + Duration::days(1), &policy),
+        Validity::Valid {
+            until: Some(valid_until)
+        }
+    );
+    assert_eq!(
+        release.check_validity(valid_until + Duration::seconds(1), &policy),
+        Validity::Expired { since: valid_until }
+    );
+
+    // Clocks may be slightly behind the archive.
+    assert!(release
+        .check_validity(date - Duration::seconds(10), &policy)
+        .is_valid());
+    assert_eq!(
+        release.check_validity(date - Duration::seconds(11), &policy),
+        Validity::NotYetValid { date }
+    );
+
+    let lenient = ValidityPolicy {
+        check_valid_until: false,
+        check_date: false,
+        ..ValidityPolicy::default()
+    };
+    assert_eq!(
+        release.check_validity(date + Duration::days(365), &lenient),
+        Validity::Valid { until: None }
+    );
+    assert!(release
+        .check_validity(date - Duration::days(1), &lenient)
+        .is_valid());
+
+    // Max-ValidTime shortens Valid-Until, and Min-ValidTime extends it.
+    let max = ValidityPolicy {
+        max_valid_time: Some(Duration::days(2)),
+        ..ValidityPolicy::default()
+    };
+    assert_eq!(release.expires(&max), Some(date + Duration::days(2)));
+
+    let min = ValidityPolicy {
+        min_valid_time: Some(Duration::days(10)),
+        ..ValidityPolicy::default()
+    };
+    assert_eq!(release.expires(&min), Some(date + Duration::days(10)));
+
+    let both = ValidityPolicy {
+        min_valid_time: Some(Duration::days(10)),
+        max_valid_time: Some(Duration::days(8)),
+        ..ValidityPolicy::default()
+    };
+    assert_eq!(release.expires(&both), Some(date + Duration::days(8)));
+
+    // Without a Valid-Until, only Max-ValidTime makes a release file expire.
+    let undated = DistRelease {
+        valid_until: None,
+        ..release.clone()
+    };
+    assert_eq!(undated.expires(&min), None);
+    assert_eq!(undated.expires(&max), Some(date + Duration::days(2)));
+}
+
+#[test]
+fn packages_index() {
+    let packages = include_str!("Packages").parse::<Packages>().unwrap();
+    assert_eq!(packages.packages.len(), 2);
+
+    let hello = &packages.packages[0];
+    assert_eq!(hello.package, "hello");
+    assert_eq!(hello.version, "2.10-3");
+    assert_eq!(hello.architecture, "amd64");
+    assert_eq!(hello.filename, "pool/main/h/hello/hello_2.10-3_amd64.deb");
+    assert_eq!(hello.size, 53100);
+    assert_eq!(
+        hello.md5sum.as_deref(),
+        Some("7d4f5b8c2a1e25f3b6c0a4e1b9d2f3a0")
+    );
+    assert_eq!(hello.depends[0][0].to_string(), "libc6 (>= 2.34)");
+    assert!(hello.pre_depends.is_empty());
+    assert_eq!(
+        hello.description.as_ref().unwrap().lines().nth(4),
+        Some(".")
+    );
+    assert_eq!(
+        hello.extra_fields[0],
+        (
+            "Maintainer".into(),
+            "Santiago Vila <sanvila@debian.org>".into()
+        )
+    );
+
+    let apt = &packages.packages[1];
+    assert_eq!(apt.md5sum, None);
+    assert_eq!(apt.depends.len(), 6);
+    assert_eq!(
+        apt.depends[0][0].version,
+        Some((VersionOp::Earlier, "3.11".into()))
+    );
+    assert_eq!(apt.pre_depends[0][0].name, "dpkg");
+    assert_eq!(apt.recommends[1][0].name, "iso-codes");
+    assert_eq!(
+        apt.description.as_deref(),
+        Some("Python 3 interface to libapt-pkg")
+    );
+
+    let error = "Package: hello\nVersion: 1.0\nArchitecture: all\nFilename: hello.deb\n"
+        .parse::<Packages>()
+        .unwrap_err();
+    match error {
+        ParseError::MissingField { position, field } => {
+            assert_eq!(position.line, 1);
+            assert_eq!(field, "Size");
+        }
+        other => panic!("unexpected error: {}", other),
+    }
+
+    let error = "Package: hello\nDepends: libc6 (>= 2.34\n"
+        .parse::<Packages>()
+        .unwrap_err();
+    match error {
+        ParseError::InvalidValue { position, .. } => {
+            assert_eq!(position.line, 2);
+            assert_eq!(position.fragment(), "libc6 (>= 2.34");
+        }
+        other => panic!("unexpected error: {}", other),
+    }
+}
+
+#[test]
+fn sources_index() {
+    let sources = include_str!("Sources").parse::<Sources>().unwrap();
+    assert_eq!(sources.sources.len(), 2);
+
+    let hello = &sources.sources[0];
+    assert_eq!(hello.package, "hello");
+    assert_eq!(hello.binary, vec!["hello"]);
+    assert_eq!(hello.directory, "pool/main/h/hello");
+    assert_eq!(hello.build_depends.len(), 2);
+    assert_eq!(hello.build_depends[1][0].profiles, vec![vec!["!nodoc"]]);
+    assert_eq!(
+        hello.sums.keys().collect::<Vec<_>>(),
+        vec![&HashAlgorithm::Md5, &HashAlgorithm::Sha256]
+    );
+    assert_eq!(
+        hello.sums[&HashAlgorithm::Md5][1],
+        ReleaseEntry {
+            sum: "a9092b63b0fb9b4605b6cd30b61f7dcb".into(),
+            size: 725946,
+            path: "hello_2.10.orig.tar.gz".into(),
+        }
+    );
+
+    let files = hello.files();
+    assert_eq!(
+        files
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "pool/main/h/hello/hello_2.10-3.debian.tar.xz",
+            "pool/main/h/hello/hello_2.10-3.dsc",
+            "pool/main/h/hello/hello_2.10.orig.tar.gz",
+        ]
+    );
+    assert!(files.iter().all(|file| file.sums.len() == 2));
+    assert_eq!(files[1].size, 1847);
+
+    let apt = &sources.sources[1];
+    assert_eq!(
+        apt.binary,
+        vec![
+            "python3-apt",
+            "python-apt-dev",
+            "python-apt-common",
+            "python-apt-doc"
+        ]
+    );
+    assert_eq!(apt.architecture, vec!["any", "all"]);
+    assert_eq!(apt.build_depends_indep[0][0].name, "python3-sphinx");
+    assert_eq!(apt.files().len(), 1);
+
+    let error = "Package: hello\nVersion: 1.0\nDirectory: pool\nFiles:\n 02d7f0e8afbd6180c4ca59327bef58f5 1847 hello.dsc\n 02d7f0e8afbd6180c4ca59327bef58f5 big hello.tar.gz\n"
+        .parse::<Sources>()
+        .unwrap_err();
+    match error {
+        ParseError::InvalidSize { position } => {
+            assert_eq!(position.line, 6);
+            assert_eq!(position.fragment(), "big");
+        }
+        other => panic!("unexpected error: {}", other),
+    }
+}
+
+#[test]
+fn contents_index() {
+    let contents = "usr/bin/hello                                           devel/hello
+usr/share/doc/hello/NEWS.gz                             devel/hello
+usr/share/fonts/truetype/dejavu/DejaVu Sans.ttf         fonts/fonts-dejavu-core,non-free/fonts/fonts-dejavu-extra
+";
+
+    let entries = ContentsReader::new(contents.as_bytes())
+        .collect::<io::Result<Vec<ContentsEntry>>>()
+        .unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].path, "usr/bin/hello");
+    assert_eq!(entries[0].packages, vec!["devel/hello"]);
+    assert_eq!(
+        entries[2].path,
+        "usr/share/fonts/truetype/dejavu/DejaVu Sans.ttf"
+    );
+    assert_eq!(
+        entries[2].package_names().collect::<Vec<_>>(),
+        vec!["fonts-dejavu-core", "fonts-dejavu-extra"]
+    );
+
+    let legacy = [
+        "This file maps each file available in the Debian GNU/Linux system to
+the package from which it originates.
+
+FILE                                                    LOCATION
+",
+        contents,
+    ]
+    .concat();
+
+    let entries = ContentsReader::new(legacy.as_bytes())
+        .collect::<io::Result<Vec<ContentsEntry>>>()
+        .unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].path, "usr/bin/hello");
+
+    let entry = ContentsReader::new(legacy.as_bytes())
+        .find_path("/usr/share/doc/hello/NEWS.gz")
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.packages, vec!["devel/hello"]);
+
+    assert!(ContentsReader::new(contents.as_bytes())
+        .find_path("/usr/bin/goodbye")
+        .unwrap()
+        .is_none());
+
+    let error = ContentsReader::new("usr/bin/hello devel/hello\nbroken\n".as_bytes())
+        .nth(1)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+// Creates an empty directory for a test to write files into.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Compresses data in the same manner as the tools which produce apt repositories.
+fn compress(compression: &Compression, data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+
+    match *compression {
+        Compression::None => output.extend_from_slice(data),
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(&mut output, flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap();
+        }
+        Compression::Xz => {
+            let mut encoder = XzEncoder::new(&mut output, 6);
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap();
+        }
+        Compression::Lzma => {
+            let options = xz2::stream::LzmaOptions::new_preset(6).unwrap();
+            let stream = xz2::stream::Stream::new_lzma_encoder(&options).unwrap();
+            let mut encoder = XzEncoder::new_stream(&mut output, stream);
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap();
+        }
+        Compression::Bzip2 => {
+            let mut encoder = BzEncoder::new(&mut output, bzip2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap();
+        }
+        Compression::Zstd => output = zstd::encode_all(data, 0).unwrap(),
+        Compression::Lz4 => {
+            let mut encoder = FrameEncoder::new(&mut output);
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap();
+        }
+        Compression::Other(_) => unreachable!(),
+    }
+
+    output
+}
+
+#[test]
+fn decompress_indices() {
+    let dir = test_dir("decompress_indices");
+    let packages = include_str!("Packages");
+
+    for compression in &[
+        Compression::None,
+        Compression::Gzip,
+        Compression::Xz,
+        Compression::Bzip2,
+        Compression::Lzma,
+        Compression::Zstd,
+        Compression::Lz4,
+    ] {
+        let name = match compression.extension() {
+            Some(extension) => ["Packages.", extension].concat(),
+            None => "Packages".to_owned(),
+        };
+
+        let data = compress(compression, packages.as_bytes());
+        fs::write(dir.join(&name), &data).unwrap();
+
+        let mut reader = IndexReader::open(dir.join(&name)).unwrap();
+        assert_eq!(reader.compression(), compression);
+        assert_eq!(reader.compressed_size(), data.len() as u64);
+
+        let mut content = String::new();
+        reader.read_to_string(&mut content).unwrap();
+        assert_eq!(content, packages);
+        assert_eq!(reader.uncompressed_size(), packages.len() as u64);
+
+        let parsed = Packages::from_file(dir.join(&name)).unwrap();
+        assert_eq!(parsed.packages.len(), 2);
+    }
+
+    fs::create_dir_all(dir.join("main/binary-amd64")).unwrap();
+    fs::copy(
+        dir.join("Packages.xz"),
+        dir.join("main/binary-amd64/Packages.xz"),
+    )
+    .unwrap();
+
+    let entry = ReleaseEntry {
+        sum: String::new(),
+        size: 0,
+        path: "main/binary-amd64/Packages.xz".into(),
+    };
+    assert_eq!(entry.compression(), Compression::Xz);
+
+    let mut content = String::new();
+    entry
+        .open(&dir)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, packages);
+
+    let error = Compression::Other("7z".into())
+        .decoder(io::empty())
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn best_compressed_form() {
+    assert_eq!(Compression::Xz.preference(), Some(0));
+    assert!(Compression::Gzip.preference() < Compression::None.preference());
+    assert_eq!(Compression::Other("7z".into()).preference(), None);
+
+    let release = RELEASE.parse::<DistRelease>().unwrap();
+    let sums = &release.sums[&HashAlgorithm::Sha256];
+
+    let entry = sums.best_entry("main/binary-amd64/Packages").unwrap();
+    assert_eq!(entry.path, "binary-amd64/Packages.xz");
+    assert_eq!(entry.compression(), Compression::Xz);
+
+    let entry = sums.best_entry("Contents-i386").unwrap();
+    assert_eq!(entry.path, "Contents-i386.xz");
+
+    let entry = sums.best_entry("main/binary-amd64/Release").unwrap();
+    assert_eq!(entry.path, "binary-amd64/Release");
+
+    assert!(sums.best_entry("main/binary-armhf/Packages").is_none());
+
+    let entries = ["Packages", "Packages.gz", "Packages.bz2", "Packages.7z"]
+        .iter()
+        .map(|name| ReleaseEntry {
+            sum: String::new(),
+            size: 0,
+            path: ["binary-amd64/", name].concat(),
+        })
+        .collect::<Vec<ReleaseEntry>>();
+
+    let variant = EntryVariant::Binary(
+        BinaryEntry::Packages(Compression::None),
+        Architecture::Amd64,
+    );
+    assert_eq!(
+        variant
+            .best_entry(&entries)
+            .map(|entry| entry.path.as_str()),
+        Some("binary-amd64/Packages.bz2")
+    );
+
+    let i386 = EntryVariant::Binary(BinaryEntry::Packages(Compression::Xz), Architecture::I386);
+    assert!(!variant.is_same_index(&i386));
+    assert!(i386.best_entry(&entries).is_none());
+}
+
+#[test]
+fn apply_pdiff_patches() {
+    let dir = test_dir("apply_pdiff_patches");
+    let sha256 = |data: &[u8]| HashAlgorithm::Sha256.digest(data).unwrap().unwrap();
+    let row = |data: &[u8], path: &str| ReleaseEntry {
+        sum: sha256(data),
+        size: data.len() as u64,
+        path: path.into(),
+    };
+
+    let old = include_str!("Packages");
+    let mid = old.replacen("Version: 2.10-3", "Version: 2.10-4", 1);
+    let new = [&mid, "\nPackage: extra\nVersion: 1.0\n"].concat();
+
+    let first = "2c\nVersion: 2.10-4\n.\n".to_owned();
+    let second = format!(
+        "{}a\n\nPackage: extra\nVersion: 1.0\n.\n",
+        mid.lines().count()
+    );
+
+    let mut index = PdiffIndex {
+        current: Some((sha256(new.as_bytes()), new.len() as u64)),
+        ..PdiffIndex::default()
+    };
+
+    for &(name, before, script) in &[("T-1", old, &first), ("T-2", &mid, &second)] {
+        let compressed = compress(&Compression::Gzip, script.as_bytes());
+        fs::write(dir.join([name, ".gz"].concat()), &compressed).unwrap();
+
+        index.history.push(row(before.as_bytes(), name));
+        index.patches.push(row(script.as_bytes(), name));
+        index
+            .download
+            .push(row(&compressed, &[name, ".gz"].concat()));
+    }
+
+    fs::write(dir.join("Packages"), old).unwrap();
+
+    let release = format!(
+        "SHA256:\n {} {} main/binary-amd64/Packages\n",
+        sha256(new.as_bytes()),
+        new.len()
+    )
+    .parse::<DistRelease>()
+    .unwrap();
+    let target = release.get("main/binary-amd64/Packages").unwrap();
+
+    assert_eq!(
+        index.patches_from(&sha256(mid.as_bytes())).unwrap().len(),
+        1
+    );
+    assert!(index.patches_from(&sha256(b"unknown")).is_none());
+
+    let content = index.apply(dir.join("Packages"), &dir, &target).unwrap();
+    assert_eq!(String::from_utf8(content).unwrap(), new);
+
+    // A local index which is already current needs no patches.
+    fs::write(dir.join("Packages"), &new).unwrap();
+    let content = index.apply(dir.join("Packages"), &dir, &target).unwrap();
+    assert_eq!(content, new.as_bytes());
+
+    // The release file must agree with the reconstructed index.
+    fs::write(dir.join("Packages"), old).unwrap();
+    let mut stale = target.clone();
+    stale
+        .sums
+        .insert(HashAlgorithm::Sha256, sha256(mid.as_bytes()));
+    match index.apply(dir.join("Packages"), &dir, &stale) {
+        Err(PdiffError::Mismatch(Mismatch::Checksum { path, .. })) => {
+            assert_eq!(path, "main/binary-amd64/Packages")
+        }
+        other => panic!("expected a checksum mismatch: {:?}", other),
+    }
+
+    // Corrupted downloads are rejected before they are applied.
+    fs::write(dir.join("T-2.gz"), b"corrupt").unwrap();
+    match index.apply(dir.join("Packages"), &dir, &target) {
+        Err(PdiffError::Mismatch(mismatch)) => assert_eq!(mismatch.path(), "T-2.gz"),
+        other => panic!("expected a mismatch: {:?}", other),
+    }
+
+    fs::write(dir.join("Packages"), "Package: unrelated\n").unwrap();
+    match index.apply(dir.join("Packages"), &dir, &target) {
+        Err(PdiffError::UnknownVersion { .. }) => (),
+        other => panic!("expected an unknown version: {:?}", other),
+    }
+}
+
+#[test]
+fn verify_dists_directory() {
+    let dir = test_dir("verify_dists_directory");
+    fs::create_dir_all(dir.join("main/binary-amd64")).unwrap();
+    fs::write(dir.join("main/binary-amd64/Packages"), "abc").unwrap();
+    fs::write(dir.join("main/binary-amd64/Release"), "abcd").unwrap();
+    fs::write(dir.join("Contents-amd64"), "abc").unwrap();
+
+    // Only the compressed form of Sources is published, as on Debian's mirrors.
+    fs::create_dir_all(dir.join("main/source")).unwrap();
+    fs::write(dir.join("main/source/Sources.xz"), "abc").unwrap();
+
+    let release = "MD5Sum:
+ 900150983cd24fb0d6963f7d28e17f72                3 Contents-amd64
+ 900150983cd24fb0d6963f7d28e17f72                3 main/binary-amd64/Packages
+ 900150983cd24fb0d6963f7d28e17f72                3 main/binary-amd64/Packages.xz
+ 900150983cd24fb0d6963f7d28e17f72                3 main/binary-amd64/Release
+ 900150983cd24fb0d6963f7d28e17f72                3 main/binary-i386/Packages
+ 900150983cd24fb0d6963f7d28e17f72                3 main/binary-i386/Packages.xz
+ 900150983cd24fb0d6963f7d28e17f72                3 main/source/Sources
+ 900150983cd24fb0d6963f7d28e17f72                3 main/source/Sources.xz
+SHA256:
+ 0000000000000000000000000000000000000000000000000000000000000000                3 Contents-amd64
+ ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad                3 main/binary-amd64/Packages
+"
+    .parse::<DistRelease>()
+    .unwrap();
+
+    let mismatches = release.verify_dir(&dir).unwrap();
+    assert_eq!(
+        mismatches,
+        vec![
+            Mismatch::Checksum {
+                path: "Contents-amd64".into(),
+                algorithm: HashAlgorithm::Sha256,
+                expected: "0000000000000000000000000000000000000000000000000000000000000000".into(),
+                found: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".into(),
+            },
+            Mismatch::Missing {
+                path: "main/binary-amd64/Packages.xz".into(),
+            },
+            Mismatch::Size {
+                path: "main/binary-amd64/Release".into(),
+                expected: 3,
+                found: 4,
+            },
+            Mismatch::Missing {
+                path: "main/binary-i386/Packages".into(),
+            },
+            Mismatch::Missing {
+                path: "main/binary-i386/Packages.xz".into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn generate_from_dists_directory() {
+    let dir = test_dir("generate_from_dists_directory");
+    for path in &[
+        "main/binary-amd64",
+        "main/binary-i386",
+        "main/source",
+        "main/i18n",
+        "main/by-hash/SHA256",
+        "contrib/binary-amd64",
+    ] {
+        fs::create_dir_all(dir.join(path)).unwrap();
+    }
+
+    for path in &[
+        "Contents-amd64.gz",
+        "main/binary-amd64/Packages",
+        "main/binary-amd64/Packages.xz",
+        "main/binary-amd64/Release",
+        "main/binary-i386/Packages.gz",
+        "main/source/Sources.xz",
+        "main/i18n/Translation-en",
+        "main/by-hash/SHA256/ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        "main/unrelated.txt",
+        "contrib/binary-amd64/Packages",
+    ] {
+        fs::write(dir.join(path), path).unwrap();
+    }
+
+    let release = ReleaseBuilder::new(&dir)
+        .origin("Pop!_OS")
+        .suite("jammy")
+        .algorithms(&[HashAlgorithm::Md5, HashAlgorithm::Sha256])
+        .build()
+        .unwrap();
+
+    assert_eq!(release.origin, Some("Pop!_OS".into()));
+    assert_eq!(
+        release.architectures,
+        Some(vec!["amd64".into(), "i386".into()])
+    );
+    assert_eq!(
+        release.components,
+        Some(vec!["contrib".into(), "main".into()])
+    );
+    assert_eq!(
+        release.sums.keys().collect::<Vec<_>>(),
+        vec![&HashAlgorithm::Md5, &HashAlgorithm::Sha256]
+    );
+
+    let sha256 = &release.sums[&HashAlgorithm::Sha256];
+    assert_eq!(sha256.iter().count(), 8);
+    assert_eq!(sha256.base["Contents-amd64"][0].size, 17);
+    assert!(!sha256.components["main"].contains_key("main/unrelated"));
+
+    assert!(release.verify_dir(&dir).unwrap().is_empty());
+
+    let written = release.to_string();
+    let parsed = written.parse::<DistRelease>().unwrap();
+    assert_eq!(parsed.sums, release.sums);
+    assert_eq!(parsed.to_string(), written);
+}
+
+#[test]
+fn inrelease_clearsigned() {
+    let inrelease = include_str!("InRelease").parse::<InRelease>().unwrap();
+    assert_eq!(inrelease.hashes, vec!["SHA512".to_owned()]);
+    assert_eq!(inrelease.release, RELEASE.parse::<DistRelease>().unwrap());
+    assert_eq!(inrelease.signed_text, RELEASE.trim_end());
+    assert!(inrelease
+        .signature
+        .starts_with(b"-----BEGIN PGP SIGNATURE-----\n"));
+    assert!(inrelease
+        .signature
+        .ends_with(b"-----END PGP SIGNATURE-----\n"));
+}
+
+#[test]
+fn inrelease_errors() {
+    let input =
+        include_str!("InRelease").replace("Date: Tue, 06 Nov 2018", "Date: Tue, 36 Nov 2018");
+    match input.parse::<InRelease>() {
+        Err(InReleaseError::Parse(why)) => {
+            assert_eq!(why.line(), 7);
+            assert_eq!(why.position().text, "Date: Tue, 36 Nov 2018 14:01:53 +0000");
+        }
+        other => panic!("expected a parse error, found {:?}", other),
+    }
+
+    let input = include_str!("InRelease").replace("-----END PGP SIGNATURE-----", "");
+    assert_eq!(
+        input.parse::<InRelease>().unwrap_err(),
+        InReleaseError::InvalidArmor("signature block is not terminated".into())
+    );
+}
 
 #[test]
 fn release_entry_binaries() {
@@ -23,7 +1320,7 @@ fn release_entry_binaries() {
                 path: "main/binary-ppc64el/Packages.gz".into(),
             },
             Some(EntryVariant::Binary(
-                BinaryEntry::Packages(Some("gz".into())),
+                BinaryEntry::Packages(Compression::Gzip),
                 Architecture::Ppc64El
             ))
         )
@@ -44,7 +1341,7 @@ fn release_entry_binaries() {
                 path: "main/binary-arm64/Packages.xz".into(),
             },
             Some(EntryVariant::Binary(
-                BinaryEntry::Packages(Some("xz".into())),
+                BinaryEntry::Packages(Compression::Xz),
                 Architecture::Arm64
             ))
         )
@@ -67,7 +1364,7 @@ fn release_entry_translations() {
                 size: 63171,
                 path: "main/i18n/Translation-en".into(),
             },
-            Some(EntryVariant::I18n(I18nEntry::Translations("en".to_owned(), None)))
+            Some(EntryVariant::I18n(I18nEntry::Translations("en".to_owned(), Compression::None)))
         )
     );
 
@@ -85,7 +1382,7 @@ fn release_entry_translations() {
                 size: 12824,
                 path: "main/i18n/Translation-en.xz".into(),
             },
-            Some(EntryVariant::I18n(I18nEntry::Translations("en".to_owned(), Some("xz".into()))))
+            Some(EntryVariant::I18n(I18nEntry::Translations("en".to_owned(), Compression::Xz)))
         )
     );
 }
@@ -108,7 +1405,7 @@ fn release_entry_contents() {
             },
             Some(EntryVariant::Contents(
                 Architecture::Arm64,
-                Some("gz".into())
+                Compression::Gzip
             ))
         )
     );
@@ -127,11 +1424,233 @@ fn release_entry_contents() {
                 size: 39115618,
                 path: "Contents-arm64".into(),
             },
-            Some(EntryVariant::Contents(Architecture::Arm64, None))
+            Some(EntryVariant::Contents(
+                Architecture::Arm64,
+                Compression::None
+            ))
         )
     );
 }
 
+#[test]
+fn release_entry_debian_installer() {
+    assert_eq!(
+        " 78b00a658cb4c13c6fa5ad3925c5b474           709486 main/debian-installer/binary-amd64/Packages.xz"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::DebianInstaller(DebianInstallerEntry::Binary(
+            BinaryEntry::Packages(Compression::Xz),
+            Architecture::Amd64
+        )))
+    );
+
+    assert_eq!(
+        " c259d415027d5f8cae4c7e1a231b5798           137080 main/debian-installer/binary-arm64/Release"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::DebianInstaller(DebianInstallerEntry::Binary(
+            BinaryEntry::Release,
+            Architecture::Arm64
+        )))
+    );
+
+    assert_eq!(
+        " 76e9ff0d84bd378f828a326fe4a57ea1           593251 Contents-udeb-amd64.gz"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::DebianInstaller(
+            DebianInstallerEntry::Contents(None, Architecture::Amd64, Compression::Gzip)
+        ))
+    );
+
+    assert_eq!(
+        " e3c8c60492687c1ec05f40ae048d05da           528170 main/Contents-udeb-i386"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::DebianInstaller(
+            DebianInstallerEntry::Contents(
+                Some("main".into()),
+                Architecture::I386,
+                Compression::None
+            )
+        ))
+    );
+
+    // The top-level contents are a different index from those of a component.
+    let variant = |path: &str| {
+        format!(" 76e9ff0d84bd378f828a326fe4a57ea1 593251 {}", path)
+            .parse::<ReleaseEntry>()
+            .unwrap()
+            .variant()
+            .unwrap()
+    };
+    assert!(
+        !variant("Contents-udeb-amd64.gz").is_same_index(&variant("main/Contents-udeb-amd64.gz"))
+    );
+
+    assert_eq!(
+        " 190c620703149944593bfadf3e2c3c99           741670 main/debian-installer/Packages"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn release_entry_component_indices() {
+    assert_eq!(
+        " 2b0a31731c80117890bd45ba0b173a4f           120757 main/Contents-amd64.gz"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::ComponentContents(
+            "main".into(),
+            Architecture::Amd64,
+            Compression::Gzip
+        ))
+    );
+
+    assert_eq!(
+        " dc13fbeeb55e57fb12e2d2cd88d839ac            23135 Contents-amd64.gz"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::Contents(
+            Architecture::Amd64,
+            Compression::Gzip
+        ))
+    );
+
+    assert_eq!(
+        " 7201cf162f3385fddafc18601b67047d           271667 universe/cnf/Commands-arm64.xz"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::Commands(
+            "universe".into(),
+            Architecture::Arm64,
+            Compression::Xz
+        ))
+    );
+
+    assert_eq!(
+        " a9dcb044fc188f2176a586795259bad0           332180 main/binary-amd64/by-hash/SHA256/0a1b2c"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::ByHash(ByHashEntry {
+            component: Some("main".into()),
+            directory: "binary-amd64".into(),
+            algorithm: HashAlgorithm::Sha256,
+            digest: "0a1b2c".into(),
+        }))
+    );
+
+    assert_eq!(
+        " 44f4fc0110e3499f7be2c4c61c0ccb7e            19264 by-hash/MD5Sum/0a1b2c"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::ByHash(ByHashEntry {
+            component: None,
+            directory: String::new(),
+            algorithm: HashAlgorithm::Md5,
+            digest: "0a1b2c".into(),
+        }))
+    );
+
+    assert_eq!(
+        " d59f536072ee659053efab13edea3ed0           500055 main/cnf/Commands"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        " ee3d4d610b33b2ff6b59cb656433ae99           313361 main/binary-amd64/by-hash/SHA256"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn release_entry_pdiff() {
+    assert_eq!(
+        " f96f1df5e193eb48331a77390ea30f05           147009 main/binary-amd64/Packages.diff/Index"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::Pdiff(Box::new(EntryVariant::Binary(
+            BinaryEntry::Packages(Compression::None),
+            Architecture::Amd64
+        ))))
+    );
+
+    assert_eq!(
+        " 7ac049ca928586872f0002b9db6fd8e1           844717 main/i18n/Translation-en.diff/Index"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::Pdiff(Box::new(EntryVariant::I18n(
+            I18nEntry::Translations("en".into(), Compression::None)
+        ))))
+    );
+
+    assert_eq!(
+        " 0a9446eeb395555b5d76fb4cac8f906d           693418 main/source/Sources.diff/Index"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        Some(EntryVariant::Pdiff(Box::new(EntryVariant::Source(
+            SourceEntry::Sources(Compression::None)
+        ))))
+    );
+
+    assert_eq!(
+        " ed8bc950656f854c064ebd3082ad7a42           267917 main/dep11/by-name.diff/Index"
+            .parse::<ReleaseEntry>()
+            .map(|r| r.variant())
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn pdiff_index() {
+    let index = include_str!("Packages.diff.Index")
+        .parse::<PdiffIndex>()
+        .unwrap();
+
+    let (sum, size) = index.current.clone().unwrap();
+    assert_eq!(sum.len(), 64);
+    assert_eq!(size, 208961);
+
+    assert_eq!(index.history.len(), 2);
+    assert_eq!(index.history[0].path, "2024-09-20-0812.47");
+    assert_eq!(index.history[0].size, 207512);
+    assert_eq!(index.patches[1].size, 903);
+    assert_eq!(index.download[1].path, "2024-09-20-2014.11.gz");
+    assert_eq!(
+        index.extra_fields,
+        vec![("X-Patch-Precedence".into(), "merged".into())]
+    );
+
+    let error = "SHA256-Current: abc 12\n"
+        .parse::<PdiffIndex>()
+        .unwrap_err();
+    match error {
+        ParseError::InvalidChecksum { position, .. } => assert_eq!(position.line, 1),
+        other => panic!("unexpected error: {}", other),
+    }
+}
+
 #[test]
 fn release_entry_dep11_components() {
     assert_eq!(
@@ -150,7 +1669,7 @@ fn release_entry_dep11_components() {
             },
             Some(EntryVariant::Dep11(Dep11Entry::Components(
                 Architecture::Ppc64El,
-                Some("yml.xz".into())
+                Compression::Xz
             )))
         )
     );
@@ -168,7 +1687,7 @@ fn release_entry_dep11_icons() {
                 pixels: 64,
                 hidpi: 2
             },
-            Some("tar.gz".into())
+            Compression::Gzip
         )))
     );
 
@@ -182,7 +1701,7 @@ fn release_entry_dep11_icons() {
                 pixels: 48,
                 hidpi: 0
             },
-            Some("tar".into())
+            Compression::None
         )))
     );
 }
@@ -203,9 +1722,334 @@ fn release_entry_other() {
                 size: 20004,
                 path: "main/source/Sources.xz".into(),
             },
-            Some(EntryVariant::Source(SourceEntry::Sources(Some(
-                "xz".into()
-            ))))
+            Some(EntryVariant::Source(SourceEntry::Sources(Compression::Xz)))
         )
     );
 }
+
+#[cfg(feature = "openpgp")]
+mod openpgp {
+    use super::RELEASE;
+    use apt_release_file::{InRelease, Keyring, VerifyError};
+
+    const FINGERPRINT: &str = "11CBFB4112831CED41A0278D63271E7B02EC409A";
+
+    fn keyring(path: &str) -> Keyring {
+        Keyring::from_files([path]).unwrap()
+    }
+
+    #[test]
+    fn inrelease_signature() {
+        let inrelease = include_str!("InRelease").parse::<InRelease>().unwrap();
+        let signer = inrelease.verify(&keyring("tests/gpg/keyring.gpg")).unwrap();
+        assert_eq!(signer.fingerprint, FINGERPRINT);
+        assert_eq!(signer.primary_fingerprint, FINGERPRINT);
+    }
+
+    #[test]
+    fn detached_signature() {
+        let keyring = keyring("tests/gpg/keyring.gpg");
+        let signature = include_bytes!("gpg/Release.gpg");
+
+        let signer = keyring.verify(RELEASE.as_bytes(), signature).unwrap();
+        assert_eq!(signer.fingerprint, FINGERPRINT);
+
+        let tampered = RELEASE.replace("cosmic", "disco");
+        match keyring.verify(tampered.as_bytes(), signature) {
+            Err(VerifyError::BadSignature { fingerprint }) => assert_eq!(fingerprint, FINGERPRINT),
+            other => panic!("expected a bad signature: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn multiple_signatures() {
+        // The first signature was made by a key which is not in the keyring.
+        let signature = include_bytes!("gpg/Release-multiple.gpg");
+        let signer = keyring("tests/gpg/keyring.gpg")
+            .verify(RELEASE.as_bytes(), signature)
+            .unwrap();
+        assert_eq!(signer.fingerprint, FINGERPRINT);
+
+        // The error of a known key is preferred over that of an unknown one.
+        match keyring("tests/gpg/expired.gpg").verify(RELEASE.as_bytes(), signature) {
+            Err(VerifyError::ExpiredKey { .. }) => (),
+            other => panic!("expected an expired key: {:?}", other),
+        }
+
+        match keyring("tests/gpg/other.asc").verify(RELEASE.as_bytes(), signature) {
+            Err(VerifyError::UnknownKey { .. }) => (),
+            other => panic!("expected an unknown key: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_key() {
+        let inrelease = include_str!("InRelease").parse::<InRelease>().unwrap();
+        match inrelease.verify(&keyring("tests/gpg/other.asc")) {
+            Err(VerifyError::UnknownKey { issuer }) => assert_eq!(issuer, FINGERPRINT),
+            other => panic!("expected an unknown key: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn expired_key() {
+        let keyring = keyring("tests/gpg/expired.gpg");
+        let signature = include_bytes!("gpg/Release-expired.gpg");
+        match keyring.verify(RELEASE.as_bytes(), signature) {
+            Err(VerifyError::ExpiredKey { expired, .. }) => {
+                assert_eq!(expired.to_rfc3339(), "2020-06-01T12:00:00+00:00")
+            }
+            other => panic!("expected an expired key: {:?}", other),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde {
+    use super::RELEASE;
+    use apt_release_file::{
+        BinaryEntry, Compression, Dep11Entry, DistRelease, EntryVariant, HashAlgorithm, I18nEntry,
+        ImageSize, Packages, PdiffIndex,
+    };
+    use deb_architectures::Architecture;
+    use serde_json::{self, json};
+
+    #[test]
+    fn release_json() {
+        let release = RELEASE.parse::<DistRelease>().unwrap();
+        let value = serde_json::to_value(&release).unwrap();
+
+        assert_eq!(value["date"], "2018-11-06T14:01:53Z");
+        assert_eq!(value["valid_until"], serde_json::Value::Null);
+        assert_eq!(value["architectures"], json!(["i386", "amd64", "all"]));
+        assert_eq!(value["fields"][1], json!(["Codename", "cosmic"]));
+        assert_eq!(value["sums"]["MD5Sum"]["order"][0], "Contents-all");
+        assert_eq!(
+            value["sums"]["SHA256"]["components"]["main"]["main/binary-amd64/Packages"][2],
+            json!({
+                "sum": "b3d65fcbaaab6bcda2d538b1729ce3686510f64ad867d35f89931f175ce399a5",
+                "size": 50360,
+                "path": "binary-amd64/Packages.xz",
+            })
+        );
+
+        let json = serde_json::to_string(&release).unwrap();
+        assert_eq!(serde_json::from_str::<DistRelease>(&json).unwrap(), release);
+
+        // Every field may be left out, as it may be in a release file.
+        assert_eq!(
+            serde_json::from_str::<DistRelease>(r#"{"suite": "stable"}"#).unwrap(),
+            DistRelease {
+                suite: Some("stable".into()),
+                ..DistRelease::default()
+            }
+        );
+    }
+
+    #[test]
+    fn entry_variant_json() {
+        let variants = [
+            (
+                EntryVariant::Binary(BinaryEntry::Packages(Compression::Xz), Architecture::Amd64),
+                json!({"binary": [{"packages": "xz"}, "amd64"]}),
+            ),
+            (
+                EntryVariant::Binary(BinaryEntry::Release, Architecture::Ppc64El),
+                json!({"binary": ["release", "ppc64el"]}),
+            ),
+            (
+                EntryVariant::Dep11(Dep11Entry::Icons(
+                    ImageSize {
+                        pixels: 64,
+                        hidpi: 2,
+                    },
+                    Compression::None,
+                )),
+                json!({"dep11": {"icons": [{"pixels": 64, "hidpi": 2}, "none"]}}),
+            ),
+        ];
+
+        for (variant, expected) in &variants {
+            let value = serde_json::to_value(variant).unwrap();
+            assert_eq!(&value, expected);
+            assert_eq!(
+                &serde_json::from_value::<EntryVariant>(value).unwrap(),
+                variant
+            );
+        }
+
+        let error =
+            serde_json::from_value::<EntryVariant>(json!({"contents": ["vax", "gz"]})).unwrap_err();
+        assert!(error.to_string().contains("unknown architecture: vax"));
+
+        let value = json!({"i18n": {"translations": ["en", null]}});
+        assert_eq!(
+            serde_json::from_value::<EntryVariant>(value).unwrap(),
+            EntryVariant::I18n(I18nEntry::Translations("en".into(), Compression::None))
+        );
+    }
+
+    // Formats such as TOML have no `null`, so none may be written for any index of a release.
+    #[test]
+    fn entry_variant_without_null() {
+        fn has_null(value: &serde_json::Value) -> bool {
+            match *value {
+                serde_json::Value::Null => true,
+                serde_json::Value::Array(ref values) => values.iter().any(has_null),
+                serde_json::Value::Object(ref map) => map.values().any(has_null),
+                _ => false,
+            }
+        }
+
+        let release = RELEASE.parse::<DistRelease>().unwrap();
+        let variants = release.sums[&HashAlgorithm::Sha256]
+            .iter()
+            .filter_map(|(_, entry)| entry.variant())
+            .collect::<Vec<EntryVariant>>();
+        assert!(variants
+            .iter()
+            .any(|variant| variant.compression() == Some(&Compression::None)));
+
+        for variant in &variants {
+            let value = serde_json::to_value(variant).unwrap();
+            assert!(!has_null(&value), "{} has a null", value);
+            assert_eq!(
+                &serde_json::from_value::<EntryVariant>(value).unwrap(),
+                variant
+            );
+        }
+    }
+
+    #[test]
+    fn packages_json() {
+        let packages = include_str!("Packages").parse::<Packages>().unwrap();
+        let value = serde_json::to_value(&packages).unwrap();
+
+        assert_eq!(
+            value["packages"][0]["depends"][0][0]["version"],
+            json!([">=", "2.34"])
+        );
+        assert_eq!(serde_json::from_value::<Packages>(value).unwrap(), packages);
+    }
+
+    #[test]
+    fn pdiff_index_json() {
+        let index = include_str!("Packages.diff.Index")
+            .parse::<PdiffIndex>()
+            .unwrap();
+        let value = serde_json::to_value(&index).unwrap();
+
+        assert_eq!(value["current"][1], 208961);
+        assert_eq!(
+            value["extra_fields"],
+            json!([["X-Patch-Precedence", "merged"]])
+        );
+        assert_eq!(serde_json::from_value::<PdiffIndex>(value).unwrap(), index);
+    }
+}
+
+#[cfg(feature = "cli")]
+mod cli {
+    use super::test_dir;
+    use std::fs;
+    use std::process::{Command, Output};
+
+    fn apt_release(args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_apt-release"))
+            .args(args)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .unwrap()
+    }
+
+    fn stdout(output: &Output) -> String {
+        String::from_utf8(output.stdout.clone()).unwrap()
+    }
+
+    #[test]
+    fn show_and_list() {
+        let output = apt_release(&["show", "tests/InRelease"]);
+        assert_eq!(output.status.code(), Some(0));
+        assert!(stdout(&output).contains("Suite:           cosmic\n"));
+        assert!(stdout(&output).contains("Files:           24\n"));
+
+        let output = apt_release(&[
+            "list",
+            "tests/Release",
+            "--arch",
+            "amd64",
+            "--variant",
+            "binary",
+            "--compression",
+            "none",
+        ]);
+        assert_eq!(output.status.code(), Some(0));
+        let paths = stdout(&output)
+            .lines()
+            .map(|line| line.rsplit(' ').next().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec!["main/binary-amd64/Packages", "main/binary-amd64/Release"]
+        );
+
+        let output = apt_release(&["json", "tests/Release"]);
+        assert_eq!(output.status.code(), Some(0));
+        assert!(stdout(&output).contains(r#""date": "2018-11-06T14:01:53Z""#));
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(apt_release(&[]).status.code(), Some(2));
+        assert_eq!(apt_release(&["publish"]).status.code(), Some(2));
+        assert_eq!(apt_release(&["show"]).status.code(), Some(2));
+        assert_eq!(
+            apt_release(&["list", "tests/Release", "--arch", "vax"])
+                .status
+                .code(),
+            Some(2)
+        );
+        assert_eq!(
+            apt_release(&["show", "tests/missing"]).status.code(),
+            Some(3)
+        );
+        assert_eq!(
+            apt_release(&["show", "tests/Packages"]).status.code(),
+            Some(3)
+        );
+
+        assert_eq!(
+            apt_release(&["diff", "tests/Release", "tests/InRelease"])
+                .status
+                .code(),
+            Some(0)
+        );
+
+        let dir = test_dir("cli_exit_codes");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Contents-amd64"), "abc").unwrap();
+        fs::write(
+            dir.join("Release"),
+            "Suite: stable
+MD5Sum:
+ 900150983cd24fb0d6963f7d28e17f72                3 Contents-amd64
+",
+        )
+        .unwrap();
+
+        let dir = dir.to_str().unwrap();
+        assert_eq!(apt_release(&["verify", dir]).status.code(), Some(0));
+
+        let output = apt_release(&["diff", "tests/Release", &[dir, "/Release"].concat()]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stdout(&output).contains("- Suite: cosmic\n+ Suite: stable\n"));
+
+        fs::write([dir, "/Contents-amd64"].concat(), "abcd").unwrap();
+        let output = apt_release(&["verify", dir]);
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            stdout(&output),
+            "Contents-amd64: expected 3 bytes, but found 4\n"
+        );
+    }
+}